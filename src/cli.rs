@@ -1,5 +1,20 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
+use crate::core::{
+    check_single_stdin, read_table, IdfScheme, IdfSource, InputFormat, InputOptions,
+};
+use crate::matcher::{GroupFallback, MatchModeKind};
+use crate::matching::output::OutputFormat;
+use crate::name::NameColumns;
+use crate::preprocess::company::{LegalForm, LEGAL_FORMS};
+use crate::preprocess::phonetic::PhoneticAlgorithm;
+use crate::preprocess::stopwords::load_stopword_list;
+use crate::preprocess::substitutions::{load_substitution_file, Substitutions};
+use crate::preprocess::{match_form, normalise_word};
+
 #[derive(Debug, StructOpt)]
 #[structopt(about = "A matchmaker for text files")]
 pub enum MatchModeEnum {
@@ -12,36 +27,157 @@ pub enum MatchModeEnum {
     /// Fuzzy match using n-grams on processed tokens
     #[structopt(name = "ngram")]
     NGramMatch {
-        #[structopt(long = "ngram-size", default_value = "2")]
+        #[structopt(name = "ngram-size", long, default_value = "2")]
         /// Length of n-grams in characters
         n_gram_length: usize,
 
+        #[structopt(long = "min-shared-ngrams", default_value = "1")]
+        /// Only score names sharing at least this many distinct n-grams
+        min_shared_ngrams: usize,
+
         #[structopt(flatten)]
         cli: Cli,
     },
     /// Fuzzy match using Levenshtein distance on processed tokens
     #[structopt(name = "lev")]
     Levenshtein {
+        #[structopt(long)]
+        /// Only score names with a token within this many edits of a from token
+        max_edit_distance: Option<usize>,
+
         #[structopt(flatten)]
         cli: Cli,
     },
     /// Fuzzy match using Damerau-Levenshtein distance on processed tokens
     #[structopt(name = "dl")]
     DamerauLevenshtein {
+        #[structopt(long)]
+        /// Only score names with a token within this many edits of a from token
+        max_edit_distance: Option<usize>,
+
+        #[structopt(flatten)]
+        cli: Cli,
+    },
+    /// Fuzzy match using Jaro-Winkler similarity on processed tokens
+    #[structopt(name = "jw")]
+    JaroWinkler {
+        #[structopt(long, default_value = "0.1", parse(try_from_str = parse_prefix_scale))]
+        /// Weight given to each common prefix character, up to four (at most 0.25)
+        prefix_scale: f64,
+
+        #[structopt(flatten)]
+        cli: Cli,
+    },
+    /// Match person names, where an initial matches any name part starting with it
+    #[structopt(name = "person")]
+    Person {
+        #[structopt(long, default_value = "0.2", parse(try_from_str = parse_initial_discount))]
+        /// Discount on the similarity of an initial and a name part starting with it
+        initial_discount: f64,
+
         #[structopt(flatten)]
         cli: Cli,
     },
+    /// Save the lookup of a match mode over to names for matching with --index
+    #[structopt(name = "index")]
+    Index {
+        #[structopt(subcommand)]
+        command: IndexCommand,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum IndexCommand {
+    /// Preprocess and weight the names in a to file, saving them as an index
+    #[structopt(name = "build")]
+    Build {
+        #[structopt(flatten)]
+        index_args: IndexArgs,
+    },
 }
 
 impl MatchModeEnum {
-    pub fn get_cli(&self) -> &Cli {
+    /// The arguments of a match subcommand
+    pub fn get_cli(&self) -> Option<&Cli> {
+        match self {
+            MatchModeEnum::TokenMatch { cli } => Some(cli),
+            MatchModeEnum::NGramMatch { cli, .. } => Some(cli),
+            MatchModeEnum::Levenshtein { cli, .. } => Some(cli),
+            MatchModeEnum::DamerauLevenshtein { cli, .. } => Some(cli),
+            MatchModeEnum::JaroWinkler { cli, .. } => Some(cli),
+            MatchModeEnum::Person { cli, .. } => Some(cli),
+            MatchModeEnum::Index { .. } => None,
+        }
+    }
+
+    /// The number of threads requested by any subcommand
+    pub fn threads(&self) -> Option<usize> {
         match self {
-            MatchModeEnum::TokenMatch { cli } => cli,
-            MatchModeEnum::NGramMatch { cli, .. } => cli,
-            MatchModeEnum::Levenshtein { cli } => cli,
-            MatchModeEnum::DamerauLevenshtein { cli } => cli,
+            MatchModeEnum::Index {
+                command: IndexCommand::Build { index_args },
+            } => index_args.threads,
+            _ => self.get_cli().and_then(|cli| cli.threads),
         }
     }
+
+    /// The match mode of a match subcommand
+    pub fn match_mode_kind(&self) -> Option<MatchModeKind> {
+        let kind = match self {
+            MatchModeEnum::TokenMatch { .. } => MatchModeKind::Token,
+            MatchModeEnum::NGramMatch {
+                n_gram_length,
+                min_shared_ngrams,
+                ..
+            } => MatchModeKind::NGram {
+                n_gram_length: *n_gram_length,
+                min_shared_ngrams: *min_shared_ngrams,
+            },
+            MatchModeEnum::Levenshtein {
+                max_edit_distance, ..
+            } => MatchModeKind::Levenshtein {
+                max_edit_distance: *max_edit_distance,
+            },
+            MatchModeEnum::DamerauLevenshtein {
+                max_edit_distance, ..
+            } => MatchModeKind::DamerauLevenshtein {
+                max_edit_distance: *max_edit_distance,
+            },
+            MatchModeEnum::JaroWinkler { prefix_scale, .. } => MatchModeKind::JaroWinkler {
+                prefix_scale: *prefix_scale,
+            },
+            MatchModeEnum::Person {
+                initial_discount, ..
+            } => MatchModeKind::Person {
+                initial_discount: *initial_discount,
+            },
+            MatchModeEnum::Index { .. } => return None,
+        };
+        Some(kind)
+    }
+}
+
+fn parse_prefix_scale(s: &str) -> Result<f64, String> {
+    check_prefix_scale(s.parse().map_err(|e| format!("{}", e))?)
+}
+
+pub fn check_prefix_scale(prefix_scale: f64) -> Result<f64, String> {
+    if (0.0..=0.25).contains(&prefix_scale) {
+        Ok(prefix_scale)
+    } else {
+        Err("the prefix scale must be between 0 and 0.25".to_string())
+    }
+}
+
+fn parse_initial_discount(s: &str) -> Result<f64, String> {
+    check_initial_discount(s.parse().map_err(|e| format!("{}", e))?)
+}
+
+pub fn check_initial_discount(initial_discount: f64) -> Result<f64, String> {
+    if (0.0..=1.0).contains(&initial_discount) {
+        Ok(initial_discount)
+    } else {
+        Err("the initial discount must be between 0 and 1".to_string())
+    }
 }
 
 #[derive(Debug, StructOpt)]
@@ -50,13 +186,154 @@ pub struct Cli {
     #[structopt(flatten)]
     pub io_args: IoArgs,
     #[structopt(flatten)]
+    pub column_args: ColumnArgs,
+    #[structopt(flatten)]
     pub preprocessing_options: PreprocessingOptions,
     #[structopt(flatten)]
     pub match_options: MatchOptions,
     #[structopt(long)]
     pub group_match: bool,
+    #[structopt(long, default_value = "none", possible_values = GroupFallback::VARIANTS)]
+    /// With --group-match, how to match from names whose group has no to names:
+    /// leave them unmatched (none) or search every to name (all)
+    pub group_fallback: GroupFallback,
+    #[structopt(long)]
+    /// Write results in from file order, so that output is identical across runs
+    pub ordered: bool,
+    #[structopt(long)]
+    /// Add an explanation column with the token pairs behind each score, their
+    /// similarities and IDF weights, and the two name norms, as JSON
+    pub explain: bool,
+    #[structopt(long, default_value = "to")]
+    /// The names to compute token IDF weights from: the to file (to), the from file
+    /// (from), both files (both), or the path of a reference file of names, read
+    /// like the to file
+    pub idf_source: String,
+    #[structopt(long, default_value = "standard", possible_values = IdfScheme::VARIANTS)]
+    /// The token weight for a token in df of N names: ln(N/df) (standard),
+    /// ln(1+N/df) (smooth), ln(1+(N-df+0.5)/(df+0.5)) (bm25), ln(1+max_df/df) (max)
+    /// or one for every token (uniform)
+    pub idf_scheme: IdfScheme,
     #[structopt(long, help = "Explicit number of threads")]
     pub threads: Option<usize>,
+    #[structopt(long)]
+    /// Read options from this TOML or YAML file, keyed by their long names;
+    /// options given on the command line take precedence
+    pub config: Option<String>,
+}
+
+impl Cli {
+    /// The IDF source, reading the names of a reference file from its to name column
+    pub fn load_idf_source(&self) -> anyhow::Result<IdfSource> {
+        check_single_stdin(&[
+            &self.io_args.from_file,
+            self.io_args.to_file.as_deref().unwrap_or_default(),
+            &self.idf_source,
+        ])?;
+        load_idf_source(
+            &self.idf_source,
+            &self.io_args.input_options(),
+            &self.column_args.to_columns().name,
+        )
+    }
+}
+
+/// Parse an IDF source, reading the names in the `name_col` column of a reference
+/// file given by its path.
+pub fn load_idf_source(
+    idf_source: &str,
+    input_options: &InputOptions,
+    name_col: &str,
+) -> anyhow::Result<IdfSource> {
+    match idf_source {
+        "to" => Ok(IdfSource::To),
+        "from" => Ok(IdfSource::From),
+        "both" => Ok(IdfSource::Both),
+        path => {
+            let table = read_table(path, input_options)?;
+            let position = table.required_column_position(name_col, path)?;
+            let names = table
+                .records
+                .map(|record| Ok(record?.swap_remove(position)))
+                .collect::<anyhow::Result<_>>()?;
+            Ok(IdfSource::Reference(names))
+        }
+    }
+}
+
+/// Arguments for building an index of to names
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct IndexArgs {
+    /// Index the names in this file (- for standard input)
+    pub to_file: String,
+    #[structopt(long, short)]
+    /// Save the index to this filepath, which cannot be - (REQUIRED)
+    pub output_file: String,
+    #[structopt(flatten)]
+    pub input_args: InputArgs,
+    #[structopt(flatten)]
+    pub column_args: ColumnArgs,
+    #[structopt(flatten)]
+    pub preprocessing_options: PreprocessingOptions,
+    #[structopt(long, default_value = "token", possible_values = MatchModeKind::VARIANTS)]
+    /// The match mode the index is for, whose lookup of weighted names it saves
+    pub mode: MatchModeKind,
+    #[structopt(name = "ngram-size", long, default_value = "2")]
+    /// With --mode=ngram, the length of n-grams in characters
+    pub n_gram_length: usize,
+    #[structopt(long)]
+    /// With --mode=lev or dl, only look up names with a token within this many edits
+    /// of a from token
+    pub max_edit_distance: Option<usize>,
+    #[structopt(long)]
+    /// Index the names by group, for matching with --group-match
+    pub group_match: bool,
+    #[structopt(long, default_value = "to")]
+    /// The names to compute token IDF weights from: the to file (to) or the path of
+    /// a reference file of names, read like the to file
+    pub idf_source: String,
+    #[structopt(long, default_value = "standard", possible_values = IdfScheme::VARIANTS)]
+    /// The token weight for a token in df of N names, as when matching
+    pub idf_scheme: IdfScheme,
+    #[structopt(long, help = "Explicit number of threads")]
+    pub threads: Option<usize>,
+    #[structopt(long)]
+    /// Read options from this TOML or YAML file, keyed by their long names;
+    /// options given on the command line take precedence
+    pub config: Option<String>,
+}
+
+impl IndexArgs {
+    /// The match mode the index is for, with the settings that shape its lookup.
+    /// Settings that only affect scoring are left at their defaults.
+    pub fn match_mode_kind(&self) -> MatchModeKind {
+        match self.mode {
+            MatchModeKind::NGram {
+                min_shared_ngrams, ..
+            } => MatchModeKind::NGram {
+                n_gram_length: self.n_gram_length,
+                min_shared_ngrams,
+            },
+            MatchModeKind::Levenshtein { .. } => MatchModeKind::Levenshtein {
+                max_edit_distance: self.max_edit_distance,
+            },
+            MatchModeKind::DamerauLevenshtein { .. } => MatchModeKind::DamerauLevenshtein {
+                max_edit_distance: self.max_edit_distance,
+            },
+            ref mode => mode.clone(),
+        }
+    }
+
+    /// The IDF source, reading the names of a reference file from its to name column
+    pub fn load_idf_source(&self) -> anyhow::Result<IdfSource> {
+        check_single_stdin(&[&self.to_file, &self.idf_source])?;
+        load_idf_source(
+            &self.idf_source,
+            &self.input_args.input_options(),
+            &self.column_args.to_columns().name,
+        )
+    }
 }
 
 // #[structopt(subcommand)]
@@ -65,17 +342,150 @@ pub struct Cli {
 #[derive(Debug, StructOpt)]
 #[structopt(rename_all = "kebab-case")]
 pub struct IoArgs {
-    /// Match names from this file...
+    /// Match names from this file (- for standard input)...
     pub from_file: String,
+    #[structopt(required_unless = "index")]
     /// ...to names in this file
-    pub to_file: String,
+    pub to_file: Option<String>,
+    #[structopt(long, conflicts_with = "to-file")]
+    /// Match to the names in an index saved by `yenta index build` for this mode
+    /// instead of a to file
+    pub index: Option<String>,
     #[structopt(long, short)]
-    /// Save matches to this filepath (REQUIRED)
+    /// Save matches to this filepath, or - for standard output (REQUIRED)
     pub output_file: String,
+    #[structopt(long, default_value = "csv", possible_values = OutputFormat::VARIANTS)]
+    /// Format of the output file
+    pub output_format: OutputFormat,
+    #[structopt(flatten)]
+    pub input_args: InputArgs,
+}
+
+impl IoArgs {
+    pub fn input_options(&self) -> InputOptions {
+        self.input_args.input_options()
+    }
 }
 
 #[derive(Debug, StructOpt)]
 #[structopt(rename_all = "kebab-case")]
+pub struct InputArgs {
+    #[structopt(long, default_value = "csv", possible_values = InputFormat::VARIANTS)]
+    /// Format of the input files
+    pub input_format: InputFormat,
+    #[structopt(long, parse(try_from_str = parse_byte))]
+    /// Field delimiter of delimited input files, e.g. '|'
+    pub delimiter: Option<u8>,
+    #[structopt(long, parse(try_from_str = parse_byte))]
+    /// Quote character of delimited input files
+    pub quote: Option<u8>,
+}
+
+impl InputArgs {
+    pub fn input_options(&self) -> InputOptions {
+        InputOptions {
+            format: self.input_format,
+            delimiter: self.delimiter,
+            quote: self.quote,
+        }
+    }
+}
+
+/// Parse a single ASCII character, accepting \t for a tab
+pub fn parse_byte(s: &str) -> Result<u8, String> {
+    match s {
+        "\\t" => Ok(b'\t'),
+        _ if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+        _ => Err(format!("{} is not a single ASCII character", s)),
+    }
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ColumnArgs {
+    #[structopt(long, default_value = "name")]
+    /// Column holding the names in both files
+    pub name_col: String,
+    #[structopt(long, default_value = "id")]
+    /// Column holding the name identifiers in both files
+    pub id_col: String,
+    #[structopt(long, number_of_values = 1)]
+    /// Column holding the group identifiers in both files (with --group-match).
+    /// Repeat to group on a composite key of several columns [default: group]
+    pub group_col: Vec<String>,
+    #[structopt(long)]
+    /// Column holding the names in the from file, overriding --name-col
+    pub from_name_col: Option<String>,
+    #[structopt(long)]
+    /// Column holding the name identifiers in the from file, overriding --id-col
+    pub from_id_col: Option<String>,
+    #[structopt(long, number_of_values = 1)]
+    /// Column holding the group identifiers in the from file, overriding --group-col.
+    /// Repeat for a composite key
+    pub from_group_col: Vec<String>,
+    #[structopt(long)]
+    /// Column holding the names in the to file, overriding --name-col
+    pub to_name_col: Option<String>,
+    #[structopt(long)]
+    /// Column holding the name identifiers in the to file, overriding --id-col
+    pub to_id_col: Option<String>,
+    #[structopt(long, number_of_values = 1)]
+    /// Column holding the group identifiers in the to file, overriding --group-col.
+    /// Repeat for a composite key
+    pub to_group_col: Vec<String>,
+    #[structopt(long, use_delimiter = true)]
+    /// Comma separated columns of the from file to copy to the output with a from_ prefix
+    pub keep_from_cols: Vec<String>,
+    #[structopt(long, use_delimiter = true)]
+    /// Comma separated columns of the to file to copy to the output with a to_ prefix
+    pub keep_to_cols: Vec<String>,
+    #[structopt(long, conflicts_with_all = &["keep-from-cols", "keep-to-cols"])]
+    /// Copy every column of both files other than the name and id to the output
+    pub keep_all: bool,
+}
+
+impl ColumnArgs {
+    /// The group columns for a file, falling back to the shared group columns and
+    /// then to the default group column.
+    fn group_columns(&self, file_group_col: &[String]) -> Vec<String> {
+        if !file_group_col.is_empty() {
+            file_group_col.to_vec()
+        } else if !self.group_col.is_empty() {
+            self.group_col.clone()
+        } else {
+            NameColumns::default().group
+        }
+    }
+
+    /// The columns to read from the from file
+    pub fn from_columns(&self) -> NameColumns {
+        NameColumns {
+            name: self
+                .from_name_col
+                .as_ref()
+                .unwrap_or(&self.name_col)
+                .clone(),
+            id: self.from_id_col.as_ref().unwrap_or(&self.id_col).clone(),
+            group: self.group_columns(&self.from_group_col),
+            keep: self.keep_from_cols.clone(),
+            keep_all: self.keep_all,
+        }
+    }
+
+    /// The columns to read from the to file
+    pub fn to_columns(&self) -> NameColumns {
+        NameColumns {
+            name: self.to_name_col.as_ref().unwrap_or(&self.name_col).clone(),
+            id: self.to_id_col.as_ref().unwrap_or(&self.id_col).clone(),
+            group: self.group_columns(&self.to_group_col),
+            keep: self.keep_to_cols.clone(),
+            keep_all: self.keep_all,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, StructOpt, Serialize, Deserialize)]
+#[structopt(rename_all = "kebab-case")]
 pub struct PreprocessingOptions {
     #[structopt(long)]
     /// Do not convert unicode characters to ASCII equivalents
@@ -85,26 +495,118 @@ pub struct PreprocessingOptions {
     #[structopt(long)]
     /// Retain non-alphabetic characters
     pub retain_non_alphabetic: bool,
-    #[structopt(long)]
-    /// Process words with SoundEx algorithm
+    #[structopt(long, conflicts_with = "phonetic")]
+    /// Process words with SoundEx algorithm (same as --phonetic=soundex)
     pub soundex: bool,
+    #[structopt(long, possible_values = PhoneticAlgorithm::VARIANTS)]
+    /// Process words with a phonetic algorithm. Double Metaphone keeps only its
+    /// primary code, while Daitch-Mokotoff keeps each code of an ambiguous spelling
+    /// as a token.
+    pub phonetic: Option<PhoneticAlgorithm>,
     #[structopt(long, short)]
     /// Trim each word to have a maximum number of characters
     pub token_length: Option<usize>,
+    #[structopt(name = "stopwords", long, number_of_values = 1)]
+    /// Remove words in this list after normalisation: a built-in list (english or
+    /// legal) or a file of words. Names made up only of stopwords keep them all.
+    pub stopword_lists: Vec<String>,
+    #[structopt(skip)]
+    /// The normalised words removed from names, filled from the stopword lists by
+    /// `load_word_lists`
+    pub stopwords: HashSet<String>,
+    #[structopt(name = "substitutions", long, number_of_values = 1)]
+    /// Replace words and phrases before removing stopwords and phonetic coding,
+    /// following a CSV file of phrase,replacement rows without a header
+    pub substitution_files: Vec<String>,
+    #[structopt(skip)]
+    /// The phrases replaced in names, filled from the substitution files by
+    /// `load_word_lists`
+    pub substitutions: Substitutions,
+    #[structopt(long)]
+    /// Treat names as company names, giving legal forms ending them a canonical
+    /// form, e.g. inc for Inc., Incorporated and INC
+    pub company: bool,
+    #[structopt(long, default_value = "keep", possible_values = LegalForm::VARIANTS)]
+    /// With --company, keep the canonical legal form as a token (keep), use it only
+    /// to rank equally scored matches (separate), or remove it (drop)
+    pub legal_form: LegalForm,
+    #[structopt(skip)]
+    /// The spellings of legal forms, filled by `load_word_lists` for --company
+    pub legal_forms: Substitutions,
+    #[structopt(long)]
+    /// Treat names as person names, splitting words at commas and periods so that
+    /// "Smith,John" and "J.R. Smith" give a token for each name part. Implied by
+    /// the person match mode.
+    pub person: bool,
 }
 
-#[derive(Debug, StructOpt)]
+impl PreprocessingOptions {
+    /// Read the stopword lists and substitution files, adding their words,
+    /// normalised like names, to the stopwords and substitutions.
+    pub fn load_word_lists(mut self) -> anyhow::Result<Self> {
+        for source in &self.stopword_lists {
+            let words = load_stopword_list(source)?;
+            let normalised: Vec<String> = words.iter().map(|w| normalise_word(w, &self)).collect();
+            self.stopwords.extend(normalised);
+        }
+        for path in &self.substitution_files {
+            for (phrase, replacement) in load_substitution_file(path)? {
+                let phrase = phrase
+                    .split_ascii_whitespace()
+                    .map(|w| match_form(w, &self))
+                    .collect();
+                self.substitutions.insert(phrase, &replacement);
+            }
+        }
+        if self.company {
+            let mut legal_forms = Substitutions::default();
+            for (spelling, canonical) in LEGAL_FORMS {
+                let phrase = spelling
+                    .split_ascii_whitespace()
+                    .map(|w| match_form(w, &self))
+                    .collect();
+                legal_forms.insert(phrase, canonical);
+            }
+            self.legal_forms = legal_forms;
+        }
+        Ok(self)
+    }
+
+    /// The phonetic algorithm to apply, if any
+    pub fn phonetic_algorithm(&self) -> Option<PhoneticAlgorithm> {
+        match self.soundex {
+            true => Some(PhoneticAlgorithm::Soundex),
+            false => self.phonetic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, StructOpt)]
 pub struct MatchOptions {
     // pub match_mode: MatchMode,
-    #[structopt(long = "minimum-match-score", short)]
+    #[structopt(name = "minimum-match-score", long, short)]
     #[structopt(default_value = "0.01")]
     /// The minimum score required to be considered a match
     pub minimum_score: f64,
-    #[structopt(long = "number-of-results", short)]
+    #[structopt(name = "number-of-results", long, short)]
     #[structopt(default_value = "1")]
     /// The number of results to output
     pub num_results: usize,
-    #[structopt(long = "include-ties-within", short = "i")]
+    #[structopt(name = "include-ties-within", long, short = "i")]
     /// Include ties within FLOAT of the nth requested result
     pub ties_within: Option<f64>,
+    #[structopt(long)]
+    /// Write a row with empty to fields and no score for each from name without a match
+    pub include_unmatched: bool,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        MatchOptions {
+            minimum_score: 0.01,
+            num_results: 1,
+            ties_within: None,
+            include_unmatched: false,
+        }
+    }
 }
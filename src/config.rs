@@ -0,0 +1,614 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use structopt::clap::ArgMatches;
+use structopt::StructOpt;
+
+use crate::cli::{
+    load_idf_source, parse_byte, ColumnArgs, MatchModeEnum, MatchOptions, PreprocessingOptions,
+};
+use crate::core::InputOptions;
+use crate::matcher::{MatchModeKind, Matcher};
+
+/// Settings for a run, read from a TOML or YAML file. Each key is the long name
+/// of a command line option, e.g. `name-col` or `number-of-results`, and takes
+/// the value the option would. Flags take `true` or `false`. The files to match
+/// or index and the output file are given on the command line, while stopword,
+/// substitution and IDF reference files can be given here.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// The match mode (token, ngram, lev, dl, jw or person) when matching from the
+    /// library or building an index. When matching on the command line the
+    /// subcommand gives the mode, which this must agree with if set.
+    pub mode: Option<String>,
+    pub ngram_size: Option<usize>,
+    pub min_shared_ngrams: Option<usize>,
+    pub max_edit_distance: Option<usize>,
+    pub prefix_scale: Option<f64>,
+    pub initial_discount: Option<f64>,
+
+    pub output_format: Option<String>,
+    pub input_format: Option<String>,
+    pub delimiter: Option<String>,
+    pub quote: Option<String>,
+
+    pub name_col: Option<String>,
+    pub id_col: Option<String>,
+    pub group_col: Option<Vec<String>>,
+    pub from_name_col: Option<String>,
+    pub from_id_col: Option<String>,
+    pub from_group_col: Option<Vec<String>>,
+    pub to_name_col: Option<String>,
+    pub to_id_col: Option<String>,
+    pub to_group_col: Option<Vec<String>>,
+    pub keep_from_cols: Option<Vec<String>>,
+    pub keep_to_cols: Option<Vec<String>>,
+    pub keep_all: Option<bool>,
+
+    pub retain_unicode: Option<bool>,
+    pub retain_non_alphabetic: Option<bool>,
+    pub soundex: Option<bool>,
+    pub phonetic: Option<String>,
+    pub token_length: Option<usize>,
+    pub stopwords: Option<Vec<String>>,
+    pub substitutions: Option<Vec<String>>,
+    pub company: Option<bool>,
+    pub legal_form: Option<String>,
+    pub person: Option<bool>,
+
+    pub minimum_match_score: Option<f64>,
+    pub number_of_results: Option<usize>,
+    pub include_ties_within: Option<f64>,
+    pub include_unmatched: Option<bool>,
+
+    pub group_match: Option<bool>,
+    pub group_fallback: Option<String>,
+    pub ordered: Option<bool>,
+    pub explain: Option<bool>,
+    pub idf_source: Option<String>,
+    pub idf_scheme: Option<String>,
+    pub threads: Option<usize>,
+}
+
+/// Options that only some subcommands accept, with the subcommands accepting them
+const SUBCOMMAND_OPTIONS: &[(&str, &[&str])] = &[
+    ("mode", &["build"]),
+    ("ngram-size", &["ngram", "build"]),
+    ("min-shared-ngrams", &["ngram"]),
+    ("max-edit-distance", &["lev", "dl", "build"]),
+    ("prefix-scale", &["jw"]),
+    ("initial-discount", &["person"]),
+    ("output-format", MATCH_SUBCOMMANDS),
+    ("minimum-match-score", MATCH_SUBCOMMANDS),
+    ("number-of-results", MATCH_SUBCOMMANDS),
+    ("include-ties-within", MATCH_SUBCOMMANDS),
+    ("include-unmatched", MATCH_SUBCOMMANDS),
+    ("group-fallback", MATCH_SUBCOMMANDS),
+    ("ordered", MATCH_SUBCOMMANDS),
+    ("explain", MATCH_SUBCOMMANDS),
+];
+
+const MATCH_SUBCOMMANDS: &[&str] = MatchModeKind::VARIANTS;
+
+/// Options that conflict with each other, so that giving any of them on the
+/// command line overrides all of them in the file
+const KEPT_COLUMN_OPTIONS: &[&str] = &["keep-from-cols", "keep-to-cols", "keep-all"];
+
+impl Config {
+    /// Read a configuration file, as YAML for a .yaml or .yml extension and as
+    /// TOML otherwise.
+    pub fn from_file(path: &str) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path).with_context(|| format!("accessing {}", path))?;
+        let extension = Path::new(path).extension().and_then(|e| e.to_str());
+        match extension {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)
+                .with_context(|| format!("reading configuration from {}", path)),
+            _ => toml::from_str(&contents)
+                .with_context(|| format!("reading configuration from {}", path)),
+        }
+    }
+
+    /// The settings as command line arguments for `subcommand`, leaving out
+    /// options for which `explicit` holds.
+    pub fn to_args<F>(&self, subcommand: &str, explicit: F) -> Vec<String>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let values = match serde_json::to_value(self) {
+            Ok(Value::Object(values)) => values,
+            _ => unreachable!("a Config always serializes to an object"),
+        };
+        let kept_columns_explicit = KEPT_COLUMN_OPTIONS.iter().any(|o| explicit(o));
+
+        let mut args = vec![];
+        for (option, value) in values {
+            let accepted = match SUBCOMMAND_OPTIONS.iter().find(|(o, _)| *o == option) {
+                Some((_, subcommands)) => subcommands.contains(&subcommand),
+                None => true,
+            };
+            let overridden = explicit(&option)
+                || (kept_columns_explicit && KEPT_COLUMN_OPTIONS.contains(&option.as_str()));
+            if !accepted || overridden {
+                continue;
+            }
+
+            match value {
+                Value::Null | Value::Bool(false) => {}
+                Value::Bool(true) => args.push(format!("--{}", option)),
+                Value::Array(values) => {
+                    for v in values {
+                        args.push(format!("--{}={}", option, plain(v)));
+                    }
+                }
+                v => args.push(format!("--{}={}", option, plain(v))),
+            }
+        }
+        args
+    }
+
+    /// Fail if the file sets a match mode other than that of `subcommand`. Building
+    /// an index takes the mode as its `--mode` option instead.
+    fn check_mode(&self, subcommand: &str) -> anyhow::Result<()> {
+        match &self.mode {
+            Some(mode) if MATCH_SUBCOMMANDS.contains(&subcommand) && mode != subcommand => {
+                anyhow::bail!("mode {} disagrees with the {} subcommand", mode, subcommand)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The match mode and its settings, taking the command line defaults for
+    /// settings left out
+    fn match_mode_kind(&self) -> anyhow::Result<MatchModeKind> {
+        let kind = match parse_setting(&self.mode, "mode")?.unwrap_or_default() {
+            MatchModeKind::NGram {
+                n_gram_length,
+                min_shared_ngrams,
+            } => MatchModeKind::NGram {
+                n_gram_length: self.ngram_size.unwrap_or(n_gram_length),
+                min_shared_ngrams: self.min_shared_ngrams.unwrap_or(min_shared_ngrams),
+            },
+            MatchModeKind::Levenshtein { .. } => MatchModeKind::Levenshtein {
+                max_edit_distance: self.max_edit_distance,
+            },
+            MatchModeKind::DamerauLevenshtein { .. } => MatchModeKind::DamerauLevenshtein {
+                max_edit_distance: self.max_edit_distance,
+            },
+            MatchModeKind::JaroWinkler { prefix_scale } => MatchModeKind::JaroWinkler {
+                prefix_scale: self.prefix_scale.unwrap_or(prefix_scale),
+            },
+            MatchModeKind::Person { initial_discount } => MatchModeKind::Person {
+                initial_discount: self.initial_discount.unwrap_or(initial_discount),
+            },
+            MatchModeKind::Token => MatchModeKind::Token,
+        };
+        Ok(kind)
+    }
+
+    fn preprocessing_options(&self) -> anyhow::Result<PreprocessingOptions> {
+        let defaults = PreprocessingOptions::default();
+        if self.soundex == Some(true) && self.phonetic.is_some() {
+            anyhow::bail!("soundex and phonetic cannot both be set");
+        }
+        Ok(PreprocessingOptions {
+            retain_unicode: self.retain_unicode.unwrap_or(defaults.retain_unicode),
+            retain_non_alphabetic: self
+                .retain_non_alphabetic
+                .unwrap_or(defaults.retain_non_alphabetic),
+            soundex: self.soundex.unwrap_or(defaults.soundex),
+            phonetic: parse_setting(&self.phonetic, "phonetic")?,
+            token_length: self.token_length,
+            stopword_lists: self.stopwords.clone().unwrap_or_default(),
+            substitution_files: self.substitutions.clone().unwrap_or_default(),
+            company: self.company.unwrap_or(defaults.company),
+            legal_form: parse_setting(&self.legal_form, "legal-form")?
+                .unwrap_or(defaults.legal_form),
+            person: self.person.unwrap_or(defaults.person),
+            ..defaults
+        })
+    }
+
+    fn match_options(&self) -> MatchOptions {
+        let defaults = MatchOptions::default();
+        MatchOptions {
+            minimum_score: self.minimum_match_score.unwrap_or(defaults.minimum_score),
+            num_results: self.number_of_results.unwrap_or(defaults.num_results),
+            ties_within: self.include_ties_within,
+            include_unmatched: self.include_unmatched.unwrap_or(defaults.include_unmatched),
+        }
+    }
+
+    fn column_args(&self) -> ColumnArgs {
+        ColumnArgs {
+            name_col: self.name_col.clone().unwrap_or_else(|| "name".to_string()),
+            id_col: self.id_col.clone().unwrap_or_else(|| "id".to_string()),
+            group_col: self.group_col.clone().unwrap_or_default(),
+            from_name_col: self.from_name_col.clone(),
+            from_id_col: self.from_id_col.clone(),
+            from_group_col: self.from_group_col.clone().unwrap_or_default(),
+            to_name_col: self.to_name_col.clone(),
+            to_id_col: self.to_id_col.clone(),
+            to_group_col: self.to_group_col.clone().unwrap_or_default(),
+            keep_from_cols: self.keep_from_cols.clone().unwrap_or_default(),
+            keep_to_cols: self.keep_to_cols.clone().unwrap_or_default(),
+            keep_all: self.keep_all.unwrap_or(false),
+        }
+    }
+
+    fn input_options(&self) -> anyhow::Result<InputOptions> {
+        let byte = |value: &Option<String>, option: &str| match value {
+            Some(v) => parse_byte(v)
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("{}: {}", option, e)),
+            None => Ok(None),
+        };
+        Ok(InputOptions {
+            format: parse_setting(&self.input_format, "input-format")?.unwrap_or_default(),
+            delimiter: byte(&self.delimiter, "delimiter")?,
+            quote: byte(&self.quote, "quote")?,
+        })
+    }
+}
+
+/// Parse a setting given by name, naming its option in any error
+fn parse_setting<T>(value: &Option<String>, option: &str) -> anyhow::Result<Option<T>>
+where
+    T: FromStr<Err = String>,
+{
+    value
+        .as_deref()
+        .map(|v| v.parse().map_err(|e| anyhow::anyhow!("{}: {}", option, e)))
+        .transpose()
+}
+
+/// A value as it would be written on the command line
+fn plain(value: Value) -> String {
+    match value {
+        Value::String(s) => s,
+        v => v.to_string(),
+    }
+}
+
+/// The first line of a clap error, without its usage
+fn clap_message(e: &structopt::clap::Error) -> &str {
+    let line = e.message.lines().next().unwrap_or_default();
+    line.trim_start_matches("error: ")
+}
+
+impl MatchModeEnum {
+    /// Parse the command line, filling in options not given on it from the
+    /// configuration file named by `--config`.
+    pub fn from_args_with_config() -> anyhow::Result<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let matches = MatchModeEnum::clap().get_matches_from(&args);
+        let opt = MatchModeEnum::from_clap(&matches);
+        let config_path = match opt.config_path() {
+            Some(path) => path.to_string(),
+            None => return Ok(opt),
+        };
+        let config = Config::from_file(&config_path)?;
+
+        // Insert the file settings right after the innermost subcommand. Every
+        // option is named by its long name, as the file's keys are.
+        let (position, subcommand, sub_matches) = innermost_subcommand(&matches);
+        config
+            .check_mode(subcommand)
+            .with_context(|| format!("applying the configuration in {}", config_path))?;
+        let config_args =
+            config.to_args(subcommand, |option| sub_matches.occurrences_of(option) > 0);
+        let args = args[..position]
+            .iter()
+            .chain(&config_args)
+            .chain(&args[position..]);
+
+        MatchModeEnum::from_iter_safe(args).map_err(|e| {
+            anyhow::anyhow!(
+                "{}, when applying the configuration in {}",
+                clap_message(&e),
+                config_path
+            )
+        })
+    }
+
+    /// The configuration file given by `--config`
+    pub fn config_path(&self) -> Option<&str> {
+        match self {
+            MatchModeEnum::Index {
+                command: crate::cli::IndexCommand::Build { index_args },
+            } => index_args.config.as_deref(),
+            _ => self.get_cli().and_then(|cli| cli.config.as_deref()),
+        }
+    }
+}
+
+/// The position after the innermost subcommand in the arguments, along with the
+/// name and matches of that subcommand. Only the innermost subcommand takes
+/// options, so the subcommands are the arguments following the program name.
+fn innermost_subcommand<'a>(matches: &'a ArgMatches<'a>) -> (usize, &'a str, &'a ArgMatches<'a>) {
+    let mut position = 1;
+    let (mut name, mut sub_matches) = ("", matches);
+    while let (sub_name, Some(sub)) = sub_matches.subcommand() {
+        position += 1;
+        name = sub_name;
+        sub_matches = sub;
+    }
+    (position, name, sub_matches)
+}
+
+impl Matcher {
+    /// Create a matcher from a configuration, taking the defaults of the command
+    /// line for settings it leaves out. Settings of other match modes and of
+    /// files, such as the output format, are ignored.
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let column_args = config.column_args();
+        let idf_source = load_idf_source(
+            config.idf_source.as_deref().unwrap_or("to"),
+            &config.input_options()?,
+            &column_args.to_columns().name,
+        )?;
+
+        Matcher::builder()
+            .mode(config.match_mode_kind()?)
+            .preprocessing_options(config.preprocessing_options()?)
+            .match_options(config.match_options())
+            .group_match(config.group_match.unwrap_or(false))
+            .group_fallback(
+                parse_setting(&config.group_fallback, "group-fallback")?.unwrap_or_default(),
+            )
+            .from_columns(column_args.from_columns())
+            .to_columns(column_args.to_columns())
+            .ordered(config.ordered.unwrap_or(false))
+            .explain(config.explain.unwrap_or(false))
+            .idf_source(idf_source)
+            .idf_scheme(parse_setting(&config.idf_scheme, "idf-scheme")?.unwrap_or_default())
+            .show_progress(true)
+            .build()
+    }
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn config_to_args() {
+        let config: Config = toml::from_str(
+            r#"
+            name-col = "conm"
+            group-col = ["country", "sic"]
+            keep-to-cols = ["state"]
+            soundex = true
+            retain-unicode = false
+            number-of-results = 3
+            prefix-scale = 0.2
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.to_args("token", |o| o == "name-col"),
+            vec![
+                "--group-col=country",
+                "--group-col=sic",
+                "--keep-to-cols=state",
+                "--soundex",
+                "--number-of-results=3",
+            ]
+        );
+        // Any kept column option on the command line overrides the file's
+        assert!(!config
+            .to_args("jw", |o| o == "keep-all")
+            .contains(&"--keep-to-cols=state".to_string()));
+        assert!(config
+            .to_args("jw", |_| false)
+            .contains(&"--prefix-scale=0.2".to_string()));
+
+        assert!(toml::from_str::<Config>("nmae-col = \"conm\"").is_err());
+    }
+
+    #[test]
+    fn matcher_from_config() {
+        let config: Config = serde_yaml::from_str(
+            "mode: lev\nmax-edit-distance: 2\nminimum-match-score: 0.5\nordered: true\n",
+        )
+        .unwrap();
+        let matcher = Matcher::from_config(&config).unwrap();
+        assert_eq!(
+            matcher.mode(),
+            &MatchModeKind::Levenshtein {
+                max_edit_distance: Some(2)
+            }
+        );
+        assert_eq!(matcher.match_options().minimum_score, 0.5);
+        assert!(matcher.ordered());
+
+        let config = Config {
+            mode: Some("fuzzy".to_string()),
+            ..Config::default()
+        };
+        assert!(Matcher::from_config(&config).is_err());
+
+        let config = Config {
+            soundex: Some(true),
+            phonetic: Some("nysiis".to_string()),
+            ..Config::default()
+        };
+        assert!(Matcher::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn command_line_stopwords_replace_config() {
+        let config: Config = toml::from_str("stopwords = [\"english\", \"legal\"]").unwrap();
+        let matches = MatchModeEnum::clap().get_matches_from([
+            "yenta",
+            "token",
+            "from.csv",
+            "to.csv",
+            "-o",
+            "out.csv",
+            "--stopwords=generic.txt",
+        ]);
+        let sub_matches = matches.subcommand_matches("token").unwrap();
+        assert!(config
+            .to_args("token", |o| sub_matches.occurrences_of(o) > 0)
+            .is_empty());
+    }
+
+    #[test]
+    fn command_line_substitutions_replace_config() {
+        let config: Config = toml::from_str("substitutions = [\"abbreviations.csv\"]").unwrap();
+        let matches = MatchModeEnum::clap().get_matches_from([
+            "yenta",
+            "index",
+            "build",
+            "to.csv",
+            "-o",
+            "out.idx",
+            "--substitutions=spellings.csv",
+        ]);
+        let sub_matches = matches
+            .subcommand_matches("index")
+            .and_then(|m| m.subcommand_matches("build"))
+            .unwrap();
+        assert!(config
+            .to_args("build", |o| sub_matches.occurrences_of(o) > 0)
+            .is_empty());
+    }
+
+    #[test]
+    fn config_keys_are_option_names() {
+        // Every field is set, so that a field added without a value fails to compile
+        let config = Config {
+            mode: Some("lev".to_string()),
+            ngram_size: Some(3),
+            min_shared_ngrams: Some(2),
+            max_edit_distance: Some(1),
+            prefix_scale: Some(0.2),
+            initial_discount: Some(0.3),
+            output_format: Some("jsonl".to_string()),
+            input_format: Some("tsv".to_string()),
+            delimiter: Some("|".to_string()),
+            quote: Some("'".to_string()),
+            name_col: Some("conm".to_string()),
+            id_col: Some("gvkey".to_string()),
+            group_col: Some(vec!["country".to_string()]),
+            from_name_col: Some("conm".to_string()),
+            from_id_col: Some("gvkey".to_string()),
+            from_group_col: Some(vec!["country".to_string()]),
+            to_name_col: Some("name".to_string()),
+            to_id_col: Some("permno".to_string()),
+            to_group_col: Some(vec!["nation".to_string()]),
+            keep_from_cols: Some(vec!["sic".to_string()]),
+            keep_to_cols: Some(vec!["state".to_string()]),
+            keep_all: Some(true),
+            retain_unicode: Some(true),
+            retain_non_alphabetic: Some(true),
+            soundex: Some(true),
+            phonetic: Some("metaphone".to_string()),
+            token_length: Some(4),
+            stopwords: Some(vec!["english".to_string()]),
+            substitutions: Some(vec!["abbreviations.csv".to_string()]),
+            company: Some(true),
+            legal_form: Some("drop".to_string()),
+            person: Some(true),
+            minimum_match_score: Some(0.5),
+            number_of_results: Some(2),
+            include_ties_within: Some(0.1),
+            include_unmatched: Some(true),
+            group_match: Some(true),
+            group_fallback: Some("all".to_string()),
+            ordered: Some(true),
+            explain: Some(true),
+            idf_source: Some("both".to_string()),
+            idf_scheme: Some("smooth".to_string()),
+            threads: Some(2),
+        };
+        let values = match serde_json::to_value(&config).unwrap() {
+            Value::Object(values) => values,
+            _ => unreachable!(),
+        };
+
+        let subcommands = MATCH_SUBCOMMANDS
+            .iter()
+            .map(|&mode| {
+                (
+                    mode,
+                    vec!["yenta", mode, "from.csv", "to.csv", "-o", "out.csv"],
+                )
+            })
+            .chain(std::iter::once((
+                "build",
+                vec!["yenta", "index", "build", "to.csv", "-o", "out.idx"],
+            )));
+        let mut accepted: Vec<String> = vec![];
+        for (subcommand, command) in subcommands {
+            for (option, value) in &values {
+                let single: Config =
+                    serde_json::from_value(serde_json::json!({ option: value })).unwrap();
+                let config_args = single.to_args(subcommand, |_| false);
+                if config_args.is_empty() {
+                    continue;
+                }
+
+                let args = command.iter().map(|a| a.to_string()).chain(config_args);
+                let matches = MatchModeEnum::clap()
+                    .get_matches_from_safe(args)
+                    .unwrap_or_else(|e| panic!("{} for {}: {}", option, subcommand, e));
+                let (_, name, sub_matches) = innermost_subcommand(&matches);
+                assert_eq!(name, subcommand);
+                assert!(
+                    sub_matches.occurrences_of(option) > 0,
+                    "{} is not the name clap knows its option by",
+                    option
+                );
+                accepted.push(option.clone());
+            }
+        }
+        for option in values.keys() {
+            assert!(
+                accepted.contains(option),
+                "no subcommand accepts {}",
+                option
+            );
+        }
+    }
+
+    #[test]
+    fn config_mode_agrees_with_subcommand() {
+        let config = Config {
+            mode: Some("lev".to_string()),
+            ..Config::default()
+        };
+        assert!(config.check_mode("lev").is_ok());
+        assert!(config.check_mode("build").is_ok());
+        let error = config.check_mode("jw").unwrap_err();
+        assert!(error.to_string().contains("disagrees"));
+        assert!(Config::default().check_mode("jw").is_ok());
+    }
+
+    #[test]
+    fn matcher_from_config_takes_command_line_defaults() {
+        for mode in MATCH_SUBCOMMANDS {
+            let config = Config {
+                mode: Some(mode.to_string()),
+                ..Config::default()
+            };
+            let mme =
+                MatchModeEnum::from_iter(&["yenta", mode, "from.csv", "to.csv", "-o", "out.csv"]);
+            assert_eq!(
+                format!("{:?}", Matcher::from_config(&config).unwrap()),
+                format!("{:?}", Matcher::from_cli(&mme).unwrap()),
+                "defaults of {}",
+                mode
+            );
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use strsim::{damerau_levenshtein, levenshtein};
+
+/// The edit distances a BkTree can be built over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EditDistance {
+    Levenshtein,
+    DamerauLevenshtein,
+}
+
+impl EditDistance {
+    pub fn distance(self, a: &str, b: &str) -> usize {
+        match self {
+            EditDistance::Levenshtein => levenshtein(a, b),
+            EditDistance::DamerauLevenshtein => damerau_levenshtein(a, b),
+        }
+    }
+}
+
+/// A Burkhard-Keller tree for finding words within an edit distance of a query.
+#[derive(Serialize, Deserialize)]
+pub struct BkTree {
+    nodes: Vec<BkNode>,
+    distance: EditDistance,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BkNode {
+    word: String,
+    children: Vec<(usize, usize)>,
+}
+
+impl BkTree {
+    pub fn new(distance: EditDistance) -> Self {
+        BkTree {
+            nodes: vec![],
+            distance,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Add a word to the tree. Words already in the tree are ignored.
+    pub fn insert(&mut self, word: &str) {
+        if self.nodes.is_empty() {
+            self.nodes.push(BkNode::new(word));
+            return;
+        }
+
+        let mut current = 0;
+        loop {
+            let d = self.distance.distance(&self.nodes[current].word, word);
+            if d == 0 {
+                return;
+            }
+
+            match self.nodes[current].child_at(d) {
+                Some(child) => current = child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(BkNode::new(word));
+                    self.nodes[current].children.push((d, child));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// All words in the tree within `max_distance` of `word`
+    pub fn find(&self, word: &str, max_distance: usize) -> Vec<&String> {
+        let mut found = vec![];
+        if self.nodes.is_empty() {
+            return found;
+        }
+
+        let mut to_visit = vec![0];
+        while let Some(current) = to_visit.pop() {
+            let node = &self.nodes[current];
+            let d = self.distance.distance(&node.word, word);
+            if d <= max_distance {
+                found.push(&node.word);
+            }
+
+            // By the triangle inequality, only children whose distance to this node
+            // is within max_distance of d can hold matches.
+            let lower = d.saturating_sub(max_distance);
+            let upper = d + max_distance;
+            to_visit.extend(
+                node.children
+                    .iter()
+                    .filter(|(cd, _)| *cd >= lower && *cd <= upper)
+                    .map(|(_, child)| *child),
+            );
+        }
+
+        found
+    }
+}
+
+impl std::fmt::Debug for BkTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BkTree")
+            .field("words", &self.nodes.len())
+            .finish()
+    }
+}
+
+impl BkNode {
+    fn new(word: &str) -> Self {
+        BkNode {
+            word: word.to_string(),
+            children: vec![],
+        }
+    }
+
+    fn child_at(&self, distance: usize) -> Option<usize> {
+        self.children
+            .iter()
+            .find(|(d, _)| *d == distance)
+            .map(|(_, child)| *child)
+    }
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn find_within_distance() {
+        let mut tree = BkTree::new(EditDistance::Levenshtein);
+        for w in [
+            "book", "books", "cake", "boo", "cape", "cart", "boon", "book",
+        ] {
+            tree.insert(w);
+        }
+        assert_eq!(tree.len(), 7);
+
+        let mut found = tree.find("bood", 1);
+        found.sort();
+        assert_eq!(found, vec!["boo", "book", "boon"]);
+
+        let mut found = tree.find("cake", 2);
+        found.sort();
+        assert_eq!(found, vec!["cake", "cape", "cart"]);
+
+        assert!(tree.find("xyz", 1).is_empty());
+    }
+}
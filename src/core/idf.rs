@@ -1,6 +1,9 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
 use counter::Counter;
+use serde::{Deserialize, Serialize};
 
 /// TokenDocument trait yields a vector of tokens representing a document for
 /// computing an Idf.
@@ -9,16 +12,28 @@ pub trait TokenDocument {
 }
 
 /// Inverse document frequency values
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Idf {
     weight_map: HashMap<String, f64>,
     weight_for_missing: f64,
 }
 
 impl Idf {
-    pub fn new<T>(docs: &Vec<T>) -> Self
+    pub fn new<T>(docs: &[T]) -> Self
     where
         T: TokenDocument,
+    {
+        Idf::from_documents(
+            docs.iter().map(|d| d.token_document()),
+            IdfScheme::default(),
+        )
+    }
+
+    /// Build an Idf from documents given as their tokens, which may repeat. Tokens
+    /// in no document are weighted as if they appeared in one.
+    pub fn from_documents<'a, I>(docs: I, scheme: IdfScheme) -> Self
+    where
+        I: IntoIterator<Item = Vec<&'a String>>,
     {
         let mut df = DocumentFrequency::new();
 
@@ -27,24 +42,24 @@ impl Idf {
         }
 
         let num_docs = df.num_docs;
-        let ln_num_docs = (num_docs as f64).ln();
+        let max_df = df.document_frequency.values().max().copied().unwrap_or(1);
 
         let idf = df
             .document_frequency
             .iter()
-            .map(|(k, v)| {
-                (
-                    k.to_string(),
-                    ln_num_docs - (*v as f64).ln(), // f64::value_from(*v).unwrap().ln(),
-                )
-            })
+            .map(|(k, v)| (k.to_string(), scheme.weight(num_docs, *v, max_df)))
             .collect();
         Idf {
             weight_map: idf,
-            weight_for_missing: ln_num_docs,
+            weight_for_missing: scheme.weight(num_docs, 1, max_df),
         }
     }
 
+    /// Whether no document had a token, leaving nothing to weight tokens by
+    pub fn is_empty(&self) -> bool {
+        self.weight_map.is_empty()
+    }
+
     pub fn lookup(&self, token: &str) -> f64 {
         self.weight_map
             .get(token)
@@ -52,6 +67,88 @@ impl Idf {
     }
 }
 
+/// Formulas for the weight of a token appearing in `df` of `N` documents
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IdfScheme {
+    /// `ln(N / df)`, which is zero for a token in every document
+    #[default]
+    Standard,
+    /// `ln(1 + N / df)`, which is always positive
+    Smooth,
+    /// The BM25 weight `ln(1 + (N - df + 0.5) / (df + 0.5))`, with `N - df`
+    /// floored at zero so that a token counted in more documents than there are,
+    /// as a token missing from an empty corpus is, keeps a positive weight
+    Probabilistic,
+    /// `ln(1 + max_df / df)`, relative to the most frequent token rather than
+    /// the number of documents
+    Max,
+    /// One for every token, ignoring document frequency
+    Uniform,
+}
+
+impl IdfScheme {
+    pub const VARIANTS: &'static [&'static str] = &["standard", "smooth", "bm25", "max", "uniform"];
+
+    /// The weight of a token appearing in `df` of `num_docs` documents, where the
+    /// most frequent token appears in `max_df`.
+    pub fn weight(&self, num_docs: usize, df: usize, max_df: usize) -> f64 {
+        let (n, df, max_df) = (num_docs as f64, df as f64, max_df as f64);
+        match self {
+            IdfScheme::Standard => n.ln() - df.ln(),
+            IdfScheme::Smooth => (1.0 + n / df).ln(),
+            IdfScheme::Probabilistic => (1.0 + ((n - df).max(0.0) + 0.5) / (df + 0.5)).ln(),
+            IdfScheme::Max => (1.0 + max_df / df).ln(),
+            IdfScheme::Uniform => 1.0,
+        }
+    }
+}
+
+impl FromStr for IdfScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(IdfScheme::Standard),
+            "smooth" => Ok(IdfScheme::Smooth),
+            "bm25" => Ok(IdfScheme::Probabilistic),
+            "max" => Ok(IdfScheme::Max),
+            "uniform" => Ok(IdfScheme::Uniform),
+            _ => Err(format!(
+                "unknown idf scheme {}, expected one of {}",
+                s,
+                IdfScheme::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for IdfScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IdfScheme::Standard => "standard",
+            IdfScheme::Smooth => "smooth",
+            IdfScheme::Probabilistic => "bm25",
+            IdfScheme::Max => "max",
+            IdfScheme::Uniform => "uniform",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The names whose tokens the Idf is computed from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum IdfSource {
+    /// The to names
+    #[default]
+    To,
+    /// The from names
+    From,
+    /// The from and to names together
+    Both,
+    /// A separate reference list of unprocessed names
+    Reference(Vec<String>),
+}
+
 /******************************************************************************/
 /*  Document   related   types                                                */
 /******************************************************************************/
@@ -71,15 +168,80 @@ impl DocumentFrequency {
         }
     }
 
-    fn add_document<T>(&mut self, doc: &T)
-    where
-        T: TokenDocument,
-    {
-        let unique_tokens: HashSet<&String> = doc.token_document().into_iter().collect();
-        //         name_processed.token_counter.keys().into_iter().collect();
+    fn add_document(&mut self, doc: Vec<&String>) {
+        let unique_tokens: HashSet<&String> = doc.into_iter().collect();
         for k in unique_tokens {
             self.document_frequency[k] += 1;
         }
         self.num_docs += 1
     }
 }
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn idf_schemes() {
+        let (a, b, c) = ("a".to_string(), "b".to_string(), "c".to_string());
+        let docs = || vec![vec![&a, &b, &a], vec![&a, &c], vec![&a], vec![&b]];
+
+        let idf = Idf::from_documents(docs(), IdfScheme::Standard);
+        assert_approx_eq!(idf.lookup("a"), (4.0f64 / 3.0).ln());
+        assert_approx_eq!(idf.lookup("c"), 4f64.ln());
+        assert_approx_eq!(idf.lookup("missing"), 4f64.ln());
+
+        let idf = Idf::from_documents(docs(), IdfScheme::Smooth);
+        assert_approx_eq!(idf.lookup("b"), 3f64.ln());
+
+        let idf = Idf::from_documents(docs(), IdfScheme::Probabilistic);
+        assert_approx_eq!(idf.lookup("a"), (1.0f64 + 1.5 / 3.5).ln());
+        assert_approx_eq!(idf.lookup("c"), (1.0f64 + 3.5 / 1.5).ln());
+
+        let idf = Idf::from_documents(docs(), IdfScheme::Max);
+        assert_approx_eq!(idf.lookup("a"), 2f64.ln());
+        assert_approx_eq!(idf.lookup("c"), 4f64.ln());
+
+        let idf = Idf::from_documents(docs(), IdfScheme::Uniform);
+        assert_eq!(idf.lookup("a"), 1.0);
+        assert_eq!(idf.lookup("missing"), 1.0);
+
+        // A single document gives a positive weight under every scheme but standard
+        for scheme in [IdfScheme::Smooth, IdfScheme::Probabilistic, IdfScheme::Max] {
+            let idf = Idf::from_documents(vec![vec![&a]], scheme);
+            assert!(idf.lookup("a") > 0.0);
+        }
+    }
+
+    #[test]
+    fn common_tokens_never_outweigh_rare_ones() {
+        // Token i appears in i of the five documents
+        let tokens: Vec<String> = (1..=5).map(|i| i.to_string()).collect();
+        let docs = || (0..5).map(|d| tokens[d..].iter().collect::<Vec<_>>());
+
+        for name in IdfScheme::VARIANTS {
+            let scheme: IdfScheme = name.parse().unwrap();
+            let idf = Idf::from_documents(docs(), scheme);
+            let weights: Vec<f64> = std::iter::once("missing")
+                .chain(tokens.iter().map(|t| t.as_str()))
+                .map(|t| idf.lookup(t))
+                .collect();
+            assert!(
+                weights.windows(2).all(|w| w[0] >= w[1]),
+                "{} weights {:?}",
+                scheme,
+                weights
+            );
+            if scheme != IdfScheme::Standard {
+                assert!(weights.iter().all(|w| *w > 0.0), "{} weights", scheme);
+            }
+        }
+
+        let idf = Idf::from_documents(vec![], IdfScheme::Probabilistic);
+        assert!(idf.lookup("missing") > 0.0);
+    }
+}
@@ -1,20 +1,233 @@
+use std::fmt;
 use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::str::FromStr;
 
 use anyhow::Context;
-use serde::de::DeserializeOwned;
+use csv::ReaderBuilder;
+use itertools::Itertools;
+use serde_json::{Map, Value};
 
-pub fn vec_from_csv<T>(file_path: &str) -> anyhow::Result<Vec<T>>
-where
-    T: DeserializeOwned,
-{
+/// Formats for reading tabular data files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Comma separated values with a header row
+    #[default]
+    Csv,
+    /// Tab separated values with a header row
+    Tsv,
+    /// One JSON object per line, with the keys as columns
+    Jsonl,
+}
+
+impl InputFormat {
+    pub const VARIANTS: &'static [&'static str] = &["csv", "tsv", "jsonl"];
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(InputFormat::Csv),
+            "tsv" => Ok(InputFormat::Tsv),
+            "jsonl" => Ok(InputFormat::Jsonl),
+            _ => Err(format!(
+                "unknown input format {}, expected one of {}",
+                s,
+                InputFormat::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InputFormat::Csv => "csv",
+            InputFormat::Tsv => "tsv",
+            InputFormat::Jsonl => "jsonl",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How to read tabular data files. The delimiter and quote characters override
+/// the defaults of the delimited formats.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputOptions {
+    pub format: InputFormat,
+    pub delimiter: Option<u8>,
+    pub quote: Option<u8>,
+}
+
+/// The header row and records of a tabular data file
+pub struct Table {
+    pub headers: Vec<String>,
+    pub records: Box<dyn Iterator<Item = anyhow::Result<Vec<String>>>>,
+}
+
+impl Table {
+    /// The position of `column` in the header row
+    pub fn column_position(&self, column: &str) -> Option<usize> {
+        self.headers.iter().position(|h| h == column)
+    }
+
+    /// The position of `column` in the header row, which must be present
+    pub fn required_column_position(&self, column: &str, file_path: &str) -> anyhow::Result<usize> {
+        self.column_position(column)
+            .with_context(|| format!("{} has no column named {}", file_path, column))
+    }
+}
+
+/// The path standing for standard input or standard output
+pub const STDIO_PATH: &str = "-";
+
+/// Fail if more than one of the input files is standard input, which can only be
+/// read once.
+pub fn check_single_stdin(file_paths: &[&str]) -> anyhow::Result<()> {
+    if file_paths.iter().filter(|p| **p == STDIO_PATH).count() > 1 {
+        anyhow::bail!("only one input file can be read from standard input");
+    }
+    Ok(())
+}
+
+/// Open a file for reading, or standard input for `-`.
+pub fn open_input(file_path: &str) -> anyhow::Result<Box<dyn Read>> {
+    if file_path == STDIO_PATH {
+        return Ok(Box::new(io::stdin()));
+    }
     let file = File::open(file_path).with_context(|| format!("accessing {}", file_path))?;
-    let mut rdr = csv::Reader::from_reader(file);
-
-    rdr.deserialize()
-        // .into_iter()
-        .map(|result| {
-            let record: T = result.with_context(|| format!("reading data from {}", file_path))?;
-            Ok(record)
-        })
-        .collect()
+    Ok(Box::new(file))
+}
+
+/// Open a tabular data file, or standard input for `-`, for reading its records
+/// as strings.
+pub fn read_table(file_path: &str, opts: &InputOptions) -> anyhow::Result<Table> {
+    let file = open_input(file_path)?;
+    match opts.format {
+        InputFormat::Csv => delimited_table(file, file_path, opts, b','),
+        InputFormat::Tsv => delimited_table(file, file_path, opts, b'\t'),
+        InputFormat::Jsonl => jsonl_table(file, file_path),
+    }
+}
+
+fn delimited_table(
+    file: Box<dyn Read>,
+    file_path: &str,
+    opts: &InputOptions,
+    default_delimiter: u8,
+) -> anyhow::Result<Table> {
+    let mut rdr = ReaderBuilder::new()
+        .delimiter(opts.delimiter.unwrap_or(default_delimiter))
+        .quote(opts.quote.unwrap_or(b'"'))
+        .from_reader(file);
+    let headers = rdr
+        .headers()
+        .with_context(|| format!("reading headers from {}", file_path))?
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+
+    let file_path = file_path.to_string();
+    let records = rdr.into_records().map(move |result| {
+        let record = result.with_context(|| format!("reading data from {}", file_path))?;
+        Ok(record.iter().map(|f| f.to_string()).collect())
+    });
+
+    Ok(Table {
+        headers,
+        records: Box::new(records),
+    })
+}
+
+/// Read a JSON Lines file, using every key in the file as a column. Missing and
+/// null values read as empty strings, and other values as their JSON text.
+fn jsonl_table(file: Box<dyn Read>, file_path: &str) -> anyhow::Result<Table> {
+    let mut objects: Vec<Map<String, Value>> = vec![];
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("reading data from {}", file_path))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let object = serde_json::from_str(&line)
+            .with_context(|| format!("reading line {} of {}", i + 1, file_path))?;
+        objects.push(object);
+    }
+
+    let headers: Vec<String> = objects
+        .iter()
+        .flat_map(|o| o.keys())
+        .unique()
+        .cloned()
+        .collect();
+
+    let columns = headers.clone();
+    let records = objects.into_iter().map(move |mut o| {
+        Ok(columns
+            .iter()
+            .map(|c| match o.remove(c) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(s)) => s,
+                Some(v) => v.to_string(),
+            })
+            .collect())
+    });
+
+    Ok(Table {
+        headers,
+        records: Box::new(records),
+    })
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::TempFile;
+
+    fn read_all(contents: &str, opts: &InputOptions) -> (Vec<String>, Vec<Vec<String>>) {
+        let file = TempFile::new("table", contents);
+        let table = read_table(file.path(), opts).unwrap();
+        let records = table.records.collect::<anyhow::Result<_>>().unwrap();
+        (table.headers, records)
+    }
+
+    #[test]
+    fn delimited_tables() {
+        let opts = InputOptions {
+            format: InputFormat::Csv,
+            delimiter: Some(b'|'),
+            quote: Some(b'\''),
+        };
+        let (headers, records) = read_all("name|id\n'Smith | Sons'|1\n", &opts);
+        assert_eq!(headers, vec!["name", "id"]);
+        assert_eq!(records, vec![vec!["Smith | Sons", "1"]]);
+
+        let opts = InputOptions {
+            format: InputFormat::Tsv,
+            ..InputOptions::default()
+        };
+        let (_, records) = read_all("name\tid\nSmith, Jones\t2\n", &opts);
+        assert_eq!(records, vec![vec!["Smith, Jones", "2"]]);
+    }
+
+    #[test]
+    fn jsonl_tables() {
+        let opts = InputOptions {
+            format: InputFormat::Jsonl,
+            ..InputOptions::default()
+        };
+        let (headers, records) = read_all(
+            "{\"name\": \"Psych\", \"id\": 1}\n\n{\"id\": 2, \"name\": \"Blueberry\", \"sic\": null, \"state\": \"CA\"}\n",
+            &opts,
+        );
+        assert_eq!(headers, vec!["name", "id", "sic", "state"]);
+        assert_eq!(
+            records,
+            vec![vec!["Psych", "1", "", ""], vec!["Blueberry", "2", "", "CA"]]
+        );
+    }
 }
@@ -79,11 +79,10 @@ mod test {
 
     #[test]
     fn test_0() {
-        let are_tied: Box<dyn Fn(&i64, &i64) -> bool> =
-            Box::new(|a: &i64, b: &i64| (a - b).abs() <= 1);
+        let are_tied: Box<EqualityTestFn<i64>> = Box::new(|a: &i64, b: &i64| (a - b).abs() <= 1);
         let mut mmth: MinMaxTieHeap<i64> = MinMaxTieHeap::new(2, are_tied);
 
-        for i in vec![1, 2, 2, 2, 3, 3, 3, 4, 5] {
+        for i in [1, 2, 2, 2, 3, 3, 3, 4, 5] {
             mmth.push(i);
         }
 
@@ -98,11 +97,10 @@ mod test {
 
     #[test]
     fn test_1() {
-        let are_tied: Box<dyn Fn(&i64, &i64) -> bool> =
-            Box::new(|a: &i64, b: &i64| (a - b).abs() <= 1);
+        let are_tied: Box<EqualityTestFn<i64>> = Box::new(|a: &i64, b: &i64| (a - b).abs() <= 1);
         let mut mmth: MinMaxTieHeap<i64> = MinMaxTieHeap::new(2, are_tied);
 
-        for i in vec![1, 2, 2, 2, 3, 3, 3, 4, 5, 5, 5] {
+        for i in [1, 2, 2, 2, 3, 3, 3, 4, 5, 5, 5] {
             mmth.push(i);
         }
 
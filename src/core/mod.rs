@@ -1,7 +1,11 @@
+pub mod bk_tree;
 pub mod idf;
 pub mod io;
 pub mod min_max_tie_heap;
+pub mod reorder_buffer;
 
+pub use bk_tree::*;
 pub use idf::*;
 pub use io::*;
 pub use min_max_tie_heap::*;
+pub use reorder_buffer::*;
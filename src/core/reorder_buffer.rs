@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+
+/// Restores the order of items arriving out of order, such as results from
+/// parallel workers. Each index from zero must be pushed exactly once.
+#[derive(Debug)]
+pub struct ReorderBuffer<T> {
+    next: usize,
+    pending: BTreeMap<usize, T>,
+}
+
+impl<T> ReorderBuffer<T> {
+    pub fn new() -> Self {
+        ReorderBuffer {
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Add the item at `index`, returning every item that is now ready in index
+    /// order.
+    pub fn push(&mut self, index: usize, item: T) -> Vec<T> {
+        self.pending.insert(index, item);
+
+        let mut ready = vec![];
+        while let Some(item) = self.pending.remove(&self.next) {
+            ready.push(item);
+            self.next += 1;
+        }
+        ready
+    }
+
+    /// The number of items waiting for an earlier index
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+impl<T> Default for ReorderBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn releases_items_in_order() {
+        let mut buffer = ReorderBuffer::new();
+        assert_eq!(buffer.push(2, "c"), Vec::<&str>::new());
+        assert_eq!(buffer.push(1, "b"), Vec::<&str>::new());
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.push(0, "a"), vec!["a", "b", "c"]);
+        assert_eq!(buffer.push(3, "d"), vec!["d"]);
+        assert!(buffer.is_empty());
+    }
+}
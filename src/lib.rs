@@ -0,0 +1,52 @@
+//! *yenta* matches names across two collections using IDF-weighted token
+//! comparisons.
+//!
+//! The command line program is a thin wrapper around this library. To embed the
+//! matcher, build a [`Matcher`] and pass it in-memory names:
+//!
+//! ```
+//! use yenta::{MatchModeKind, Matcher, NameUngrouped};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let matcher = Matcher::builder()
+//!     .mode(MatchModeKind::NGram {
+//!         n_gram_length: 3,
+//!         min_shared_ngrams: 1,
+//!     })
+//!     .build()?;
+//!
+//! let from_names = vec![NameUngrouped::new("Jon Smyth".to_string(), "1".to_string())];
+//! let to_names = vec![
+//!     NameUngrouped::new("John Smith".to_string(), "a".to_string()),
+//!     NameUngrouped::new("Jane Doe".to_string(), "b".to_string()),
+//! ];
+//!
+//! let results = matcher.match_names(from_names, to_names);
+//! assert_eq!(results[0].to_id, "a");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Group matching is selected by passing [`NameGrouped`] values, in which case
+//! each from-name is only compared to to-names sharing its group.
+
+pub mod cli;
+pub mod config;
+pub mod core;
+pub mod matcher;
+pub mod matching;
+pub mod name;
+pub mod preprocess;
+#[cfg(test)]
+mod testing;
+
+pub use crate::cli::{MatchOptions, PreprocessingOptions};
+pub use crate::config::Config;
+pub use crate::core::{IdfScheme, IdfSource};
+pub use crate::matcher::{GroupFallback, MatchModeKind, Matcher, MatcherBuilder};
+pub use crate::matching::output::OutputFormat;
+pub use crate::matching::prebuilt::{IndexSummary, PrebuiltIndex};
+pub use crate::matching::result::{MatchBatch, MatchResultSend};
+pub use crate::matching::summary::MatchSummary;
+pub use crate::name::{NameGrouped, NameUngrouped, ScoreExplanation};
+pub use crate::preprocess::company::LegalForm;
@@ -1,33 +1,47 @@
-mod cli;
-mod core;
-mod matching;
-mod name;
-mod preprocess;
+use std::process;
 
+use anyhow::Context;
 use colored::Colorize;
-use structopt::StructOpt;
 
-use crate::cli::MatchModeEnum;
-use crate::matching::execute_match;
-use crate::name::{NameGrouped, NameUngrouped};
+use yenta::cli::{IndexCommand, MatchModeEnum};
+use yenta::Matcher;
 
 fn main() {
-    let opt = MatchModeEnum::from_args();
+    let opt = MatchModeEnum::from_args_with_config().unwrap_or_else(|e| exit_with_error(e));
 
-    if let Some(threads) = opt.get_cli().threads {
+    if let Some(threads) = opt.threads() {
         rayon::ThreadPoolBuilder::new()
             .num_threads(threads)
             .build_global()
-            .unwrap();
+            .context("starting the thread pool")
+            .unwrap_or_else(|e| exit_with_error(e));
     }
 
-    let res = match opt.get_cli().group_match {
-        true => execute_match::<NameGrouped>(&opt),
-        false => execute_match::<NameUngrouped>(&opt),
+    let res = match &opt {
+        MatchModeEnum::Index {
+            command: IndexCommand::Build { index_args },
+        } => Matcher::from_index_args(index_args)
+            .and_then(|m| {
+                m.index_file(
+                    &index_args.to_file,
+                    &index_args.input_args.input_options(),
+                    &index_args.output_file,
+                )
+            })
+            .map(|summary| summary.to_string()),
+        _ => Matcher::from_cli(&opt)
+            .and_then(|m| m.match_files(&opt.get_cli().unwrap().io_args))
+            .map(|summary| summary.to_string()),
     };
 
-    if let Err(e) = res {
-        let error = "error".red();
-        println!("{}: {}", error, e)
+    match res {
+        Ok(summary) => eprintln!("{}", summary),
+        Err(e) => exit_with_error(e),
     }
 }
+
+fn exit_with_error(e: anyhow::Error) -> ! {
+    let error = "error".red();
+    eprintln!("{}: {:#}", error, e);
+    process::exit(1);
+}
@@ -0,0 +1,896 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::mpsc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{
+    check_initial_discount, check_prefix_scale, IndexArgs, IoArgs, MatchModeEnum, MatchOptions,
+    PreprocessingOptions,
+};
+use crate::core::InputOptions;
+use crate::core::{IdfScheme, IdfSource};
+use crate::matching::prebuilt::IndexSummary;
+use crate::matching::result::{MatchBatch, MatchResultSend};
+use crate::matching::summary::MatchSummary;
+use crate::matching::{dispatch_match, execute_index, execute_match, MatchableName};
+use crate::name::{NameColumns, NameGrouped, NameUngrouped};
+
+/// The match mode, and its mode specific settings, used by a Matcher.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum MatchModeKind {
+    /// Exact match on processed tokens
+    #[default]
+    Token,
+    /// Fuzzy match using n-grams on processed tokens, scoring only names that
+    /// share at least `min_shared_ngrams` distinct n-grams
+    NGram {
+        n_gram_length: usize,
+        min_shared_ngrams: usize,
+    },
+    /// Fuzzy match using Levenshtein distance on processed tokens, scoring only
+    /// names with a token within `max_edit_distance` edits of a from token
+    Levenshtein { max_edit_distance: Option<usize> },
+    /// Fuzzy match using Damerau-Levenshtein distance on processed tokens, scoring
+    /// only names with a token within `max_edit_distance` edits of a from token
+    DamerauLevenshtein { max_edit_distance: Option<usize> },
+    /// Fuzzy match using Jaro-Winkler similarity on processed tokens
+    JaroWinkler { prefix_scale: f64 },
+    /// Match person names, where an initial matches any token starting with its
+    /// letter at a similarity of `1 - initial_discount`. Names are split into
+    /// their parts as with `PreprocessingOptions::person`.
+    Person { initial_discount: f64 },
+}
+
+impl MatchModeKind {
+    /// The names of the match modes, as given by their subcommands
+    pub const VARIANTS: &'static [&'static str] = &["token", "ngram", "lev", "dl", "jw", "person"];
+}
+
+/// A match mode by name, with the default settings of its subcommand
+impl FromStr for MatchModeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "token" => Ok(MatchModeKind::Token),
+            "ngram" => Ok(MatchModeKind::NGram {
+                n_gram_length: 2,
+                min_shared_ngrams: 1,
+            }),
+            "lev" => Ok(MatchModeKind::Levenshtein {
+                max_edit_distance: None,
+            }),
+            "dl" => Ok(MatchModeKind::DamerauLevenshtein {
+                max_edit_distance: None,
+            }),
+            "jw" => Ok(MatchModeKind::JaroWinkler { prefix_scale: 0.1 }),
+            "person" => Ok(MatchModeKind::Person {
+                initial_discount: 0.2,
+            }),
+            _ => Err(format!(
+                "unknown match mode {}, expected one of {}",
+                s,
+                MatchModeKind::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for MatchModeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MatchModeKind::Token => "token",
+            MatchModeKind::NGram { .. } => "ngram",
+            MatchModeKind::Levenshtein { .. } => "lev",
+            MatchModeKind::DamerauLevenshtein { .. } => "dl",
+            MatchModeKind::JaroWinkler { .. } => "jw",
+            MatchModeKind::Person { .. } => "person",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How group matching treats from-names whose group has no to-names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupFallback {
+    /// Leave the from-names unmatched
+    #[default]
+    None,
+    /// Match the from-names against every to-name
+    All,
+}
+
+impl GroupFallback {
+    pub const VARIANTS: &'static [&'static str] = &["none", "all"];
+}
+
+impl FromStr for GroupFallback {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(GroupFallback::None),
+            "all" => Ok(GroupFallback::All),
+            _ => Err(format!(
+                "unknown group fallback {}, expected one of {}",
+                s,
+                GroupFallback::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for GroupFallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GroupFallback::None => "none",
+            GroupFallback::All => "all",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A configured name matcher. Use `Matcher::builder` to construct one.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    mode: MatchModeKind,
+    preprocessing_options: PreprocessingOptions,
+    match_options: MatchOptions,
+    group_match: bool,
+    group_fallback: GroupFallback,
+    from_columns: NameColumns,
+    to_columns: NameColumns,
+    ordered: bool,
+    explain: bool,
+    idf_source: IdfSource,
+    idf_scheme: IdfScheme,
+    show_progress: bool,
+}
+
+impl Matcher {
+    pub fn builder() -> MatcherBuilder {
+        MatcherBuilder::default()
+    }
+
+    /// Create a matcher from parsed command line arguments, reading any files they
+    /// name.
+    pub fn from_cli(mme: &MatchModeEnum) -> anyhow::Result<Self> {
+        let (cli, mode) = match (mme.get_cli(), mme.match_mode_kind()) {
+            (Some(cli), Some(mode)) => (cli, mode),
+            _ => anyhow::bail!("only the match subcommands create a matcher"),
+        };
+        Matcher::builder()
+            .mode(mode)
+            .preprocessing_options(cli.preprocessing_options.clone())
+            .match_options(cli.match_options.clone())
+            .group_match(cli.group_match)
+            .group_fallback(cli.group_fallback)
+            .from_columns(cli.column_args.from_columns())
+            .to_columns(cli.column_args.to_columns())
+            .ordered(cli.ordered)
+            .explain(cli.explain)
+            .idf_source(cli.load_idf_source()?)
+            .idf_scheme(cli.idf_scheme)
+            .show_progress(true)
+            .build()
+    }
+
+    /// Create a matcher for building an index from parsed command line arguments.
+    pub fn from_index_args(args: &IndexArgs) -> anyhow::Result<Self> {
+        Matcher::builder()
+            .mode(args.match_mode_kind())
+            .preprocessing_options(args.preprocessing_options.clone())
+            .group_match(args.group_match)
+            .to_columns(args.column_args.to_columns())
+            .idf_source(args.load_idf_source()?)
+            .idf_scheme(args.idf_scheme)
+            .build()
+    }
+
+    pub fn mode(&self) -> &MatchModeKind {
+        &self.mode
+    }
+
+    pub fn preprocessing_options(&self) -> &PreprocessingOptions {
+        &self.preprocessing_options
+    }
+
+    pub fn match_options(&self) -> &MatchOptions {
+        &self.match_options
+    }
+
+    pub fn group_match(&self) -> bool {
+        self.group_match
+    }
+
+    pub fn group_fallback(&self) -> &GroupFallback {
+        &self.group_fallback
+    }
+
+    pub fn from_columns(&self) -> &NameColumns {
+        &self.from_columns
+    }
+
+    pub fn to_columns(&self) -> &NameColumns {
+        &self.to_columns
+    }
+
+    pub fn ordered(&self) -> bool {
+        self.ordered
+    }
+
+    pub fn explain(&self) -> bool {
+        self.explain
+    }
+
+    pub fn idf_source(&self) -> &IdfSource {
+        &self.idf_source
+    }
+
+    pub fn idf_scheme(&self) -> IdfScheme {
+        self.idf_scheme
+    }
+
+    pub fn show_progress(&self) -> bool {
+        self.show_progress
+    }
+
+    /// Match each of the from names to the to names, returning all results. Use
+    /// `NameGrouped` names to restrict matches to names within the same group.
+    pub fn match_names<N: MatchableName>(
+        &self,
+        from_names: Vec<N>,
+        to_names: Vec<N>,
+    ) -> Vec<MatchResultSend> {
+        self.match_names_with_summary(from_names, to_names).0
+    }
+
+    /// Match the names as `match_names` does, also returning a summary of the
+    /// match, such as the from names whose group has no to names.
+    pub fn match_names_with_summary<N: MatchableName>(
+        &self,
+        from_names: Vec<N>,
+        to_names: Vec<N>,
+    ) -> (Vec<MatchResultSend>, MatchSummary) {
+        let (tx, rx) = mpsc::channel();
+        let summary = self
+            .match_names_with_sender(from_names, to_names, tx)
+            .expect("the receiver is held until matching finishes");
+        let mut batches: Vec<MatchBatch> = rx.into_iter().collect();
+        if self.ordered {
+            batches.sort_by_key(|b| b.from_index);
+        }
+        let results = batches.into_iter().flat_map(|b| b.results).collect();
+        (results, summary)
+    }
+
+    /// Match each of the from names to the to names, sending a batch of results
+    /// for each from name down the channel as they become available. Batches
+    /// arrive in any order, and carry the position of their from name. Matching
+    /// stops with an error once the receiver hangs up.
+    pub fn match_names_with_sender<N: MatchableName>(
+        &self,
+        from_names: Vec<N>,
+        to_names: Vec<N>,
+        tx: mpsc::Sender<MatchBatch>,
+    ) -> anyhow::Result<MatchSummary> {
+        dispatch_match(self, from_names, to_names, tx)
+    }
+
+    /// Match the files given in `io_args`, writing the results to the output file.
+    pub fn match_files(&self, io_args: &IoArgs) -> anyhow::Result<MatchSummary> {
+        match self.group_match {
+            true => execute_match::<NameGrouped>(self, io_args),
+            false => execute_match::<NameUngrouped>(self, io_args),
+        }
+    }
+
+    /// Preprocess the names in `to_file` and save them as an index at `index_path`,
+    /// for matching with `IoArgs::index` in place of the to file.
+    pub fn index_file(
+        &self,
+        to_file: &str,
+        input_options: &InputOptions,
+        index_path: &str,
+    ) -> anyhow::Result<IndexSummary> {
+        match self.group_match {
+            true => execute_index::<NameGrouped>(self, to_file, input_options, index_path),
+            false => execute_index::<NameUngrouped>(self, to_file, input_options, index_path),
+        }
+    }
+}
+
+/// Builder for a Matcher. Unset values take their command line defaults.
+#[derive(Debug, Default)]
+pub struct MatcherBuilder {
+    mode: MatchModeKind,
+    preprocessing_options: PreprocessingOptions,
+    match_options: MatchOptions,
+    group_match: bool,
+    group_fallback: GroupFallback,
+    from_columns: NameColumns,
+    to_columns: NameColumns,
+    ordered: bool,
+    explain: bool,
+    idf_source: IdfSource,
+    idf_scheme: IdfScheme,
+    show_progress: bool,
+}
+
+impl MatcherBuilder {
+    pub fn mode(mut self, mode: MatchModeKind) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn preprocessing_options(mut self, preprocessing_options: PreprocessingOptions) -> Self {
+        self.preprocessing_options = preprocessing_options;
+        self
+    }
+
+    pub fn match_options(mut self, match_options: MatchOptions) -> Self {
+        self.match_options = match_options;
+        self
+    }
+
+    /// Read group identifiers when matching files, restricting matches to names
+    /// within the same group.
+    pub fn group_match(mut self, group_match: bool) -> Self {
+        self.group_match = group_match;
+        self
+    }
+
+    /// How to match grouped from-names whose group has no to-names.
+    pub fn group_fallback(mut self, group_fallback: GroupFallback) -> Self {
+        self.group_fallback = group_fallback;
+        self
+    }
+
+    /// The columns holding the name, id and group in the from file when matching files.
+    pub fn from_columns(mut self, from_columns: NameColumns) -> Self {
+        self.from_columns = from_columns;
+        self
+    }
+
+    /// The columns holding the name, id and group in the to file when matching files.
+    pub fn to_columns(mut self, to_columns: NameColumns) -> Self {
+        self.to_columns = to_columns;
+        self
+    }
+
+    /// Write results in the order of the from names, rather than as they are
+    /// found.
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// Attach an explanation of the score to each result, listing the token pairs
+    /// chosen, their similarities and IDF weights, and the two name norms.
+    pub fn explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    /// The names to compute token IDF weights from, by default the to names.
+    pub fn idf_source(mut self, idf_source: IdfSource) -> Self {
+        self.idf_source = idf_source;
+        self
+    }
+
+    /// The formula weighting tokens by their document frequency.
+    pub fn idf_scheme(mut self, idf_scheme: IdfScheme) -> Self {
+        self.idf_scheme = idf_scheme;
+        self
+    }
+
+    /// Display a progress bar on the terminal while matching.
+    pub fn show_progress(mut self, show_progress: bool) -> Self {
+        self.show_progress = show_progress;
+        self
+    }
+
+    /// Create the matcher, reading the stopword lists and substitution files named
+    /// by the preprocessing options. Fails for mode settings out of range, such
+    /// as an n-gram length below two.
+    pub fn build(self) -> anyhow::Result<Matcher> {
+        match self.mode {
+            MatchModeKind::NGram { n_gram_length, .. } if n_gram_length < 2 => {
+                anyhow::bail!("the n-gram size must be at least 2")
+            }
+            MatchModeKind::JaroWinkler { prefix_scale } => {
+                check_prefix_scale(prefix_scale).map_err(anyhow::Error::msg)?;
+            }
+            MatchModeKind::Person { initial_discount } => {
+                check_initial_discount(initial_discount).map_err(anyhow::Error::msg)?;
+            }
+            _ => {}
+        }
+
+        let mut preprocessing_options = self.preprocessing_options;
+        if let MatchModeKind::Person { .. } = self.mode {
+            preprocessing_options.person = true;
+        }
+        let preprocessing_options = preprocessing_options.load_word_lists()?;
+
+        Ok(Matcher {
+            mode: self.mode,
+            preprocessing_options,
+            match_options: self.match_options,
+            group_match: self.group_match,
+            group_fallback: self.group_fallback,
+            from_columns: self.from_columns,
+            to_columns: self.to_columns,
+            ordered: self.ordered,
+            explain: self.explain,
+            idf_source: self.idf_source,
+            idf_scheme: self.idf_scheme,
+            show_progress: self.show_progress,
+        })
+    }
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cli::IndexCommand;
+    use crate::preprocess::company::LegalForm;
+    use crate::preprocess::phonetic::PhoneticAlgorithm;
+    use crate::testing::{grouped, ungrouped, TempFile};
+    use structopt::StructOpt;
+
+    #[test]
+    fn match_names_in_memory() {
+        for mode in [
+            MatchModeKind::Token,
+            MatchModeKind::NGram {
+                n_gram_length: 2,
+                min_shared_ngrams: 1,
+            },
+            MatchModeKind::Levenshtein {
+                max_edit_distance: None,
+            },
+            MatchModeKind::DamerauLevenshtein {
+                max_edit_distance: Some(2),
+            },
+            MatchModeKind::JaroWinkler { prefix_scale: 0.1 },
+            MatchModeKind::Person {
+                initial_discount: 0.2,
+            },
+        ] {
+            let matcher = Matcher::builder().mode(mode).build().unwrap();
+            let mut results = matcher.match_names(
+                ungrouped(&[("Shawn Spencer", "1"), ("Burton Guster", "2")]),
+                ungrouped(&[
+                    ("SPENCER, SHAWN", "a"),
+                    ("Guster Burton", "b"),
+                    ("Carlton Lassiter", "c"),
+                ]),
+            );
+            results.sort_by(|a, b| a.from_id.cmp(&b.from_id));
+
+            let pairs: Vec<_> = results
+                .iter()
+                .map(|r| (r.from_id.as_str(), r.to_id.as_str()))
+                .collect();
+            assert_eq!(pairs, vec![("1", "a"), ("2", "b")]);
+        }
+    }
+
+    #[test]
+    fn mode_settings_checked() {
+        for mode in [
+            MatchModeKind::NGram {
+                n_gram_length: 1,
+                min_shared_ngrams: 1,
+            },
+            MatchModeKind::JaroWinkler { prefix_scale: 0.3 },
+            MatchModeKind::Person {
+                initial_discount: -0.1,
+            },
+        ] {
+            assert!(Matcher::builder().mode(mode).build().is_err());
+        }
+    }
+
+    #[test]
+    fn match_names_explained() {
+        for mode in [
+            MatchModeKind::Token,
+            MatchModeKind::NGram {
+                n_gram_length: 2,
+                min_shared_ngrams: 1,
+            },
+            MatchModeKind::Levenshtein {
+                max_edit_distance: None,
+            },
+            MatchModeKind::JaroWinkler { prefix_scale: 0.1 },
+        ] {
+            let results = Matcher::builder()
+                .mode(mode)
+                .explain(true)
+                .build()
+                .unwrap()
+                .match_names(
+                    ungrouped(&[("Shawn Spencer Spencer", "1")]),
+                    ungrouped(&[("SPENCER, SHAWN", "a"), ("Guster Burton", "b")]),
+                );
+            assert_eq!(results[0].to_id, "a");
+
+            // The explanation reproduces the score, with the extra spencer unpaired
+            let explanation = results[0].explanation.as_ref().unwrap();
+            let score_in_common: f64 = explanation
+                .pairs
+                .iter()
+                .map(|p| p.similarity * p.from_weight * p.to_weight)
+                .sum();
+            assert_eq!(explanation.score, results[0].score.unwrap());
+            assert!(
+                (score_in_common / (explanation.from_norm * explanation.to_norm)
+                    - explanation.score)
+                    .abs()
+                    < 1e-12
+            );
+            assert_eq!(explanation.pairs.len(), 2);
+            assert_eq!(
+                explanation.pairs[0].from_token,
+                explanation.pairs[0].to_token
+            );
+        }
+
+        let results = Matcher::builder().build().unwrap().match_names(
+            ungrouped(&[("Shawn Spencer", "1")]),
+            ungrouped(&[("SPENCER, SHAWN", "a"), ("Guster Burton", "b")]),
+        );
+        assert!(results[0].explanation.is_none());
+    }
+
+    #[test]
+    fn match_names_with_idf_source() {
+        let corp_weight = |idf_source: IdfSource| {
+            let results = Matcher::builder()
+                .idf_source(idf_source)
+                .explain(true)
+                .ordered(true)
+                .build()
+                .unwrap()
+                .match_names(
+                    ungrouped(&[
+                        ("Acme Corp", "1"),
+                        ("Beta Corp", "2"),
+                        ("Gamma Corp", "3"),
+                        ("Delta Inc", "4"),
+                    ]),
+                    ungrouped(&[("Acme Corp", "a"), ("Zeta Inc", "b")]),
+                );
+            let explanation = results[0].explanation.as_ref().unwrap();
+            let pair = explanation.pairs.iter().find(|p| p.from_token == "corp");
+            pair.unwrap().from_weight
+        };
+
+        assert_eq!(corp_weight(IdfSource::To), 2f64.ln());
+        assert_eq!(corp_weight(IdfSource::From), 4f64.ln() - 3f64.ln());
+        assert_eq!(corp_weight(IdfSource::Both), 6f64.ln() - 4f64.ln());
+        let reference = vec![
+            "Acme Corp".to_string(),
+            "Psych".to_string(),
+            "Gus".to_string(),
+        ];
+        assert_eq!(corp_weight(IdfSource::Reference(reference)), 3f64.ln());
+    }
+
+    #[test]
+    fn match_names_with_separate_legal_form() {
+        // Building the matcher loads the legal forms
+        let preprocessing_options = PreprocessingOptions {
+            company: true,
+            legal_form: LegalForm::Separate,
+            ..PreprocessingOptions::default()
+        };
+        let results = Matcher::builder()
+            .preprocessing_options(preprocessing_options.clone())
+            .idf_scheme(IdfScheme::Smooth)
+            .build()
+            .unwrap()
+            .match_names(
+                ungrouped(&[("Apple, Inc.", "1")]),
+                ungrouped(&[
+                    ("Apple Corporation", "a"),
+                    ("APPLE INCORPORATED", "b"),
+                    ("Pear Inc", "c"),
+                ]),
+            );
+
+        // The legal form breaks the tie between the equally scored apples
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].to_id, "b");
+        assert_eq!(results[0].score, Some(1.0));
+
+        let preprocessing_options = PreprocessingOptions {
+            stopword_lists: vec!["missing-stopwords.txt".to_string()],
+            ..preprocessing_options
+        };
+        assert!(Matcher::builder()
+            .preprocessing_options(preprocessing_options)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn match_person_names_with_initials() {
+        let mut results = Matcher::builder()
+            .mode(MatchModeKind::Person {
+                initial_discount: 0.2,
+            })
+            .build()
+            .unwrap()
+            .match_names(
+                ungrouped(&[("J. Smith", "1"), ("Smith,John", "2"), ("Doe, J.R.", "3")]),
+                ungrouped(&[
+                    ("Mary Smith", "a"),
+                    ("John Smith", "b"),
+                    ("Jack Doe", "c"),
+                    ("Jane Roe", "d"),
+                ]),
+            );
+        results.sort_by(|a, b| a.from_id.cmp(&b.from_id));
+
+        let pairs: Vec<_> = results
+            .iter()
+            .map(|r| (r.from_id.as_str(), r.to_id.as_str()))
+            .collect();
+        assert_eq!(pairs, vec![("1", "b"), ("2", "b"), ("3", "c")]);
+        assert!(results[0].score.unwrap() < 1.0);
+        assert_eq!(results[1].score.map(|s| s.round()), Some(1.0));
+    }
+
+    #[test]
+    fn match_names_including_unmatched() {
+        let match_options = MatchOptions {
+            include_unmatched: true,
+            ..MatchOptions::default()
+        };
+        let mut results = Matcher::builder()
+            .match_options(match_options)
+            .build()
+            .unwrap()
+            .match_names(
+                ungrouped(&[("Shawn Spencer", "1"), ("Juliet O'Hara", "2")]),
+                ungrouped(&[("SPENCER, SHAWN", "a"), ("Guster Burton", "b")]),
+            );
+        results.sort_by(|a, b| a.from_id.cmp(&b.from_id));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].to_id, "a");
+        assert!(results[0].score.is_some());
+        assert_eq!(results[1].from_name, "Juliet O'Hara");
+        assert_eq!(results[1].to_id, "");
+        assert_eq!(results[1].score, None);
+    }
+
+    #[test]
+    fn match_names_ordered() {
+        let from_names: Vec<_> = (0..200)
+            .map(|i| NameUngrouped::new(format!("Shawn Spencer {}", i), i.to_string()))
+            .collect();
+        let to_names = ungrouped(&[
+            ("Henry Spencer", "a"),
+            ("Shawn Spencer", "b"),
+            ("Shawn Spencer", "c"),
+            ("Gus", "d"),
+        ]);
+
+        let results = Matcher::builder()
+            .ordered(true)
+            .build()
+            .unwrap()
+            .match_names(from_names, to_names);
+
+        let from_ids: Vec<_> = results.iter().map(|r| r.from_id.clone()).collect();
+        let expected: Vec<_> = (0..200).map(|i| i.to_string()).collect();
+        assert_eq!(from_ids, expected);
+
+        // Equal scores are broken in favour of the earlier to name
+        assert!(results.iter().all(|r| r.to_id == "b"));
+    }
+
+    #[test]
+    fn match_names_within_groups() {
+        let from_names = grouped(&[("Shawn Spencer", "1", "psych")]);
+        let to_names = grouped(&[
+            ("Shawn Spencer", "a", "other"),
+            ("Henry Spencer", "b", "psych"),
+            ("Carlton Lassiter", "c", "psych"),
+        ]);
+
+        let results = Matcher::builder()
+            .build()
+            .unwrap()
+            .match_names(from_names, to_names);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].to_id, "b");
+    }
+
+    #[test]
+    fn match_names_with_group_fallback() {
+        let from_names = || grouped(&[("Shawn Spencer", "1", "psych"), ("Gus", "2", "blue")]);
+        let to_names = || {
+            grouped(&[
+                ("Shawn Spencer", "a", "other"),
+                ("Henry Spencer", "b", "psych"),
+                ("Carlton Lassiter", "c", "psych"),
+                ("Burton Gus Guster", "d", "pineapple"),
+            ])
+        };
+
+        // Without a fallback, orphaned names are reported and left unmatched
+        let (results, summary) = Matcher::builder()
+            .build()
+            .unwrap()
+            .match_names_with_summary(from_names(), to_names());
+        assert_eq!(summary.from_names, 2);
+        assert_eq!(summary.orphaned_names(), 1);
+        assert_eq!(summary.orphaned_groups.get("blue"), Some(&1));
+        assert_eq!(results.len(), 1);
+
+        // With a fallback, they are matched against every to name
+        let mut results = Matcher::builder()
+            .group_fallback(GroupFallback::All)
+            .explain(true)
+            .build()
+            .unwrap()
+            .match_names(from_names(), to_names());
+        results.sort_by(|a, b| a.from_id.cmp(&b.from_id));
+        let pairs: Vec<_> = results
+            .iter()
+            .map(|r| (r.from_id.as_str(), r.to_id.as_str()))
+            .collect();
+        assert_eq!(pairs, vec![("1", "b"), ("2", "d")]);
+        let explanation = results[1].explanation.as_ref().unwrap();
+        assert_eq!(Some(explanation.score), results[1].score);
+    }
+
+    #[test]
+    fn match_files_with_empty_idf_source() {
+        let names = TempFile::new("names", "name,id\nAcme Corp,1\nBeta Corp,2\n");
+        let reference = TempFile::new("reference", "name\n");
+        let output = TempFile::unwritten("output");
+        for mode in ["token", "lev", "jw", "ngram"] {
+            let mme = MatchModeEnum::from_iter([
+                "yenta",
+                mode,
+                names.path(),
+                names.path(),
+                "-o",
+                output.path(),
+                "--idf-source",
+                reference.path(),
+            ]);
+            let matcher = Matcher::from_cli(&mme).unwrap();
+            let error = matcher.match_files(&mme.get_cli().unwrap().io_args);
+            assert!(error.unwrap_err().to_string().contains("--idf-source"));
+        }
+    }
+
+    #[test]
+    fn match_files_with_index() {
+        let to_names = TempFile::new("to", "firm,id\nAcme Corp,1\nBeta Corp,2\n");
+        let from_names = TempFile::new("from", "name,id\nAcme Corporation,a\n");
+        let index = TempFile::unwritten("index");
+        let output = TempFile::unwritten("output");
+        let build = |output_file: &str| {
+            let mme = MatchModeEnum::from_iter([
+                "yenta",
+                "index",
+                "build",
+                to_names.path(),
+                "-o",
+                output_file,
+                "--mode=ngram",
+                "--ngram-size=3",
+                "--name-col=firm",
+            ]);
+            let index_args = match &mme {
+                MatchModeEnum::Index {
+                    command: IndexCommand::Build { index_args },
+                } => index_args,
+                _ => unreachable!(),
+            };
+            Matcher::from_index_args(index_args)?.index_file(
+                &index_args.to_file,
+                &index_args.input_args.input_options(),
+                &index_args.output_file,
+            )
+        };
+        assert_eq!(build(index.path()).unwrap().names, 2);
+        let error = build("-").unwrap_err().to_string();
+        assert!(error.contains("not standard output"));
+        assert!(!std::path::Path::new("-").exists());
+
+        let match_index = |args: &[&str]| {
+            let mme = MatchModeEnum::from_iter(
+                [
+                    "yenta",
+                    args[0],
+                    from_names.path(),
+                    "--index",
+                    index.path(),
+                    "-o",
+                    output.path(),
+                ]
+                .iter()
+                .chain(&args[1..]),
+            );
+            Matcher::from_cli(&mme)?.match_files(&mme.get_cli().unwrap().io_args)
+        };
+        match_index(&["ngram", "--ngram-size=3", "--to-name-col=firm"]).unwrap();
+        assert!(output.contents().contains("Acme Corporation,a,Acme Corp,1"));
+
+        // The index only suits the mode, lookup settings and columns it was built with
+        let error = |args: &[&str]| match_index(args).unwrap_err().to_string();
+        assert!(error(&["lev", "--to-name-col=firm"]).contains("--mode=ngram"));
+        assert!(error(&["ngram", "--to-name-col=firm"]).contains("--ngram-size=3"));
+        assert!(error(&["ngram", "--ngram-size=3"]).contains("to name, id"));
+    }
+
+    #[test]
+    fn standard_input_read_once() {
+        for args in [
+            [
+                "yenta",
+                "token",
+                "-",
+                "to.csv",
+                "-o",
+                "-",
+                "--idf-source",
+                "-",
+            ],
+            [
+                "yenta",
+                "token",
+                "from.csv",
+                "-",
+                "-o",
+                "-",
+                "--idf-source",
+                "-",
+            ],
+        ] {
+            let error = Matcher::from_cli(&MatchModeEnum::from_iter(args));
+            assert!(error.unwrap_err().to_string().contains("standard input"));
+        }
+    }
+
+    #[test]
+    fn soundex_conflicts_with_phonetic() {
+        let args = ["yenta", "token", "from.csv", "to.csv", "-o", "out.csv"];
+        let soundex = MatchModeEnum::from_iter(args.iter().chain(&["--soundex"]));
+        assert_eq!(
+            soundex
+                .get_cli()
+                .unwrap()
+                .preprocessing_options
+                .phonetic_algorithm(),
+            Some(PhoneticAlgorithm::Soundex)
+        );
+        let both = args.iter().chain(&["--soundex", "--phonetic", "nysiis"]);
+        assert!(MatchModeEnum::from_iter_safe(both).is_err());
+    }
+
+    #[test]
+    fn matching_stops_when_receiver_hangs_up() {
+        let (tx, rx) = mpsc::channel();
+        drop(rx);
+        let error = Matcher::builder().build().unwrap().match_names_with_sender(
+            ungrouped(&[("Acme Corp", "1"), ("Beta Corp", "2")]),
+            ungrouped(&[("Acme Corp", "a")]),
+            tx,
+        );
+        assert!(error.unwrap_err().to_string().contains("hung up"));
+    }
+}
@@ -1,184 +1,472 @@
-mod mode;
-mod result;
+pub mod index;
+pub mod mode;
+pub mod output;
+pub mod prebuilt;
+pub mod result;
+pub mod summary;
 
-use std::fs::OpenOptions;
+use std::collections::{BTreeMap, HashSet};
 use std::marker::Send;
-use std::sync::mpsc;
-use std::thread;
+use std::sync::mpsc::{self, SendError};
 
-use anyhow::Context;
-use csv::WriterBuilder;
-use indicatif::ParallelProgressIterator;
+use indicatif::{ParallelProgressIterator, ProgressBar};
 use rayon::prelude::*;
 
-use crate::cli::{MatchModeEnum, MatchOptions, PreprocessingOptions};
-use crate::core::{Idf, MinMaxTieHeap};
+use crate::cli::{IoArgs, MatchOptions};
+use crate::core::{check_single_stdin, Idf, IdfSource, InputOptions, MinMaxTieHeap, TokenDocument};
+use crate::matcher::{GroupFallback, MatchModeKind, Matcher};
+use crate::matching::index::Candidates;
 use crate::matching::mode::MatchMode;
 use crate::matching::mode::PotentialMatches;
-use crate::matching::mode::{DamerauLevenshteinMatch, LevenshteinMatch, NGramMatch, TokenMatch};
-use crate::matching::result::{MatchResult, MatchResultSend};
-use crate::name::{NameContainer, UnprocessedName};
-use crate::preprocess::{prep_name, prep_names};
+use crate::matching::mode::{
+    DamerauLevenshteinMatch, JaroWinklerMatch, LevenshteinMatch, NGramMatch, PersonMatch,
+    TokenMatch,
+};
+use crate::matching::output::spawn_writer;
+use crate::matching::prebuilt::{IndexSummary, PrebuiltIndex};
+use crate::matching::result::{MatchBatch, MatchResultSend, RankedMatch};
+use crate::matching::summary::MatchSummary;
+use crate::name::{NameContainer, NameProcessed, UnprocessedName};
+use crate::preprocess::{prep_name, prep_names, prep_words};
 
-// pub fn execute_match<N>(mme: &MatchModeEnum) -> Result<(), Box<dyn Error>>
-pub fn execute_match<N>(mme: &MatchModeEnum) -> anyhow::Result<()>
-where
+/// Name types that can be matched under every match mode.
+pub trait MatchableName:
+    UnprocessedName
+    + Send
+    + Sync
+    + PotentialMatches<TokenMatch>
+    + PotentialMatches<NGramMatch>
+    + PotentialMatches<LevenshteinMatch>
+    + PotentialMatches<DamerauLevenshteinMatch>
+    + PotentialMatches<JaroWinklerMatch>
+    + PotentialMatches<PersonMatch>
+{
+}
+
+impl<N> MatchableName for N where
     N: UnprocessedName
         + Send
         + Sync
         + PotentialMatches<TokenMatch>
         + PotentialMatches<NGramMatch>
         + PotentialMatches<LevenshteinMatch>
-        + PotentialMatches<DamerauLevenshteinMatch>,
-    <N as PotentialMatches<TokenMatch>>::Lookup: Sync,
-    <N as PotentialMatches<NGramMatch>>::Lookup: Sync,
-    <N as PotentialMatches<LevenshteinMatch>>::Lookup: Sync,
-    <N as PotentialMatches<DamerauLevenshteinMatch>>::Lookup: Sync,
+        + PotentialMatches<DamerauLevenshteinMatch>
+        + PotentialMatches<JaroWinklerMatch>
+        + PotentialMatches<PersonMatch>
 {
-    let (tx, rx): (
-        mpsc::Sender<Vec<MatchResultSend>>,
-        mpsc::Receiver<Vec<MatchResultSend>>,
-    ) = mpsc::channel();
-
-    // Bring in shared command line options
-    let io_args = &mme.get_cli().io_args;
-    let prep_opts = &mme.get_cli().preprocessing_options;
-    let match_opts = &mme.get_cli().match_options;
-
-    // Load in both name files. This is done immediately and eagerly to ensure that
-    // all names in both files are properly formed.
-    let to_names = N::from_csv(&io_args.to_file)?;
-    let from_names = N::from_csv(&io_args.from_file)?;
-
-    // Spawn the CSV writer
-    spawn_csv_writer(&io_args.output_file, rx)?;
-
-    // Dispatch by match mode
-    dispatch_match(mme, from_names, to_names, prep_opts, match_opts, tx);
-    Ok(())
 }
 
-pub fn dispatch_match<N>(
-    mme: &MatchModeEnum,
+pub fn execute_match<N: MatchableName>(
+    matcher: &Matcher,
+    io_args: &IoArgs,
+) -> anyhow::Result<MatchSummary> {
+    check_single_stdin(&[
+        &io_args.from_file,
+        io_args.to_file.as_deref().unwrap_or_default(),
+    ])?;
+    if io_args.index.is_some() && *matcher.idf_source() != IdfSource::To {
+        anyhow::bail!("the IDF of an index is set when building it, not when matching");
+    }
+
+    run_with_mode::<N, _>(matcher, MatchFiles { matcher, io_args })
+}
+
+/// Preprocess the to names in `to_file` and save the lookup the matcher's mode
+/// builds over them, along with their Idf, as an index at `index_path`. The from
+/// names are unknown when building an index, so the IDF source must be the to
+/// names or a reference list.
+pub fn execute_index<N: MatchableName>(
+    matcher: &Matcher,
+    to_file: &str,
+    input_options: &InputOptions,
+    index_path: &str,
+) -> anyhow::Result<IndexSummary> {
+    if matches!(matcher.idf_source(), IdfSource::From | IdfSource::Both) {
+        anyhow::bail!("an index can only take its IDF from the to names or a reference file");
+    }
+
+    let to_file = N::from_file(to_file, matcher.to_columns(), input_options)?;
+    let to_names = prep_names(to_file.names, matcher.preprocessing_options());
+    let idf = source_idf::<N>(matcher, &[], &to_names);
+    check_idf(&idf)?;
+
+    let summary = IndexSummary {
+        names: to_names.len(),
+        tokens: to_names
+            .iter()
+            .flat_map(|n| n.token_counter().keys())
+            .collect::<HashSet<_>>()
+            .len(),
+    };
+    run_with_mode(
+        matcher,
+        SaveIndex {
+            matcher,
+            to_names,
+            kept_columns: to_file.kept_columns,
+            idf,
+            index_path,
+        },
+    )?;
+    Ok(summary)
+}
+
+/// Match the names, reporting grouped from-names whose group has no to-names.
+/// These are left unmatched, or matched against the index of every group with
+/// the `All` group fallback. Every from-name sends one batch, identified by its
+/// position in `from_names`.
+pub fn dispatch_match<N: MatchableName>(
+    matcher: &Matcher,
     from_names: Vec<N>,
     to_names: Vec<N>,
-    prep_opts: &PreprocessingOptions,
-    match_opts: &MatchOptions,
-    tx: mpsc::Sender<Vec<MatchResultSend>>,
-) where
-    N: UnprocessedName
-        + Send
-        + Sync
-        + PotentialMatches<TokenMatch>
-        + PotentialMatches<NGramMatch>
-        + PotentialMatches<LevenshteinMatch>
-        + PotentialMatches<DamerauLevenshteinMatch>,
-    <N as PotentialMatches<TokenMatch>>::Lookup: Sync,
-    <N as PotentialMatches<NGramMatch>>::Lookup: Sync,
-    <N as PotentialMatches<LevenshteinMatch>>::Lookup: Sync,
-    <N as PotentialMatches<DamerauLevenshteinMatch>>::Lookup: Sync,
-{
-    // Run the match
-    match mme {
-        MatchModeEnum::TokenMatch { .. } => {
-            match_vec_to_generic(TokenMatch, from_names, to_names, prep_opts, match_opts, tx)
-        }
-        MatchModeEnum::NGramMatch { n_gram_length, .. } => match_vec_to_generic(
-            NGramMatch::new(*n_gram_length),
+    tx: mpsc::Sender<MatchBatch>,
+) -> anyhow::Result<MatchSummary> {
+    run_with_mode(
+        matcher,
+        MatchNames {
+            matcher,
             from_names,
             to_names,
-            prep_opts,
-            match_opts,
             tx,
-        ),
-        MatchModeEnum::Levenshtein { .. } => match_vec_to_generic(
-            LevenshteinMatch,
+        },
+    )
+    .map_err(|_| anyhow::anyhow!("the receiver of the match results hung up"))
+}
+
+/// A task run with the concrete match mode a matcher is set to
+trait ModeTask<N> {
+    type Output;
+
+    fn run<M>(self, match_mode: M) -> Self::Output
+    where
+        M: MatchMode<N> + Sync,
+        M::MatchableData: Send + Sync,
+        N: PotentialMatches<M>;
+}
+
+fn run_with_mode<N: MatchableName, T: ModeTask<N>>(matcher: &Matcher, task: T) -> T::Output {
+    match matcher.mode() {
+        MatchModeKind::Token => task.run(TokenMatch),
+        MatchModeKind::NGram {
+            n_gram_length,
+            min_shared_ngrams,
+        } => task.run(NGramMatch::new(*n_gram_length).with_min_shared_ngrams(*min_shared_ngrams)),
+        MatchModeKind::Levenshtein { max_edit_distance } => {
+            task.run(LevenshteinMatch::new(*max_edit_distance))
+        }
+        MatchModeKind::DamerauLevenshtein { max_edit_distance } => {
+            task.run(DamerauLevenshteinMatch::new(*max_edit_distance))
+        }
+        MatchModeKind::JaroWinkler { prefix_scale } => {
+            task.run(JaroWinklerMatch::new(*prefix_scale))
+        }
+        MatchModeKind::Person { initial_discount } => task.run(PersonMatch::new(*initial_discount)),
+    }
+}
+
+/// Match the from file against a prebuilt index or the to file, writing the
+/// results to the output file
+struct MatchFiles<'a> {
+    matcher: &'a Matcher,
+    io_args: &'a IoArgs,
+}
+
+impl<N: MatchableName> ModeTask<N> for MatchFiles<'_> {
+    type Output = anyhow::Result<MatchSummary>;
+
+    fn run<M>(self, match_mode: M) -> Self::Output
+    where
+        M: MatchMode<N> + Sync,
+        M::MatchableData: Send + Sync,
+        N: PotentialMatches<M>,
+    {
+        let MatchFiles { matcher, io_args } = self;
+        let (tx, rx): (mpsc::Sender<MatchBatch>, mpsc::Receiver<MatchBatch>) = mpsc::channel();
+
+        // Load in the to names, as the lookup of a prebuilt index or by
+        // preprocessing the to file, and then the from names. This is done
+        // immediately and eagerly to ensure that all names in both files are
+        // properly formed.
+        let input_options = io_args.input_options();
+        let (lookup, to_kept_columns, from_file, idf) = match (&io_args.index, &io_args.to_file) {
+            (Some(index_path), _) => {
+                let index =
+                    PrebuiltIndex::<<N as PotentialMatches<M>>::Lookup>::load(index_path, matcher)?;
+                let from_file =
+                    N::from_file(&io_args.from_file, matcher.from_columns(), &input_options)?;
+                (index.lookup, index.kept_columns, from_file, index.idf)
+            }
+            (None, Some(to_file)) => {
+                let to_file = N::from_file(to_file, matcher.to_columns(), &input_options)?;
+                let to_names = prep_names(to_file.names, matcher.preprocessing_options());
+                let from_file =
+                    N::from_file(&io_args.from_file, matcher.from_columns(), &input_options)?;
+                let from_names: Vec<&N> = from_file.names.iter().collect();
+                let idf = source_idf(matcher, &from_names, &to_names);
+                check_idf(&idf)?;
+                let lookup =
+                    <N as PotentialMatches<M>>::to_names_weighted(&match_mode, to_names, &idf);
+                (lookup, to_file.kept_columns, from_file, idf)
+            }
+            (None, None) => anyhow::bail!("either a to file or an index is required"),
+        };
+
+        // Spawn the output writer
+        let headers = MatchResultSend::headers_with_explanation(
+            &from_file.kept_columns,
+            &to_kept_columns,
+            matcher.explain(),
+        );
+        let writer = spawn_writer(
+            &io_args.output_file,
+            io_args.output_format,
+            headers,
+            matcher.ordered(),
+            rx,
+        )?;
+
+        // Matching drops every sender, so the writer finishes once all results are
+        // written.
+        let matched = match_lookup(&match_mode, matcher, from_file.names, &lookup, &idf, tx);
+
+        // The writer only hangs up early on an error, which joining it reports
+        writer
+            .join()
+            .map_err(|_| anyhow::anyhow!("the output writer panicked"))??;
+        matched.map_err(|_| {
+            anyhow::anyhow!("the output writer stopped before every result was written")
+        })
+    }
+}
+
+/// Match names given in memory, sending the results down `tx`
+struct MatchNames<'a, N> {
+    matcher: &'a Matcher,
+    from_names: Vec<N>,
+    to_names: Vec<N>,
+    tx: mpsc::Sender<MatchBatch>,
+}
+
+impl<N: MatchableName> ModeTask<N> for MatchNames<'_, N> {
+    type Output = Result<MatchSummary, SendError<MatchBatch>>;
+
+    fn run<M>(self, match_mode: M) -> Self::Output
+    where
+        M: MatchMode<N> + Sync,
+        M::MatchableData: Send + Sync,
+        N: PotentialMatches<M>,
+    {
+        let MatchNames {
+            matcher,
             from_names,
             to_names,
-            prep_opts,
-            match_opts,
             tx,
-        ),
-        MatchModeEnum::DamerauLevenshtein { .. } => match_vec_to_generic(
-            DamerauLevenshteinMatch,
-            from_names,
+        } = self;
+        let to_names = prep_names(to_names, matcher.preprocessing_options());
+        let from_all: Vec<&N> = from_names.iter().collect();
+        let idf = source_idf(matcher, &from_all, &to_names);
+        let lookup = <N as PotentialMatches<M>>::to_names_weighted(&match_mode, to_names, &idf);
+
+        match_lookup(&match_mode, matcher, from_names, &lookup, &idf, tx)
+    }
+}
+
+/// Build the lookup of the matcher's mode over preprocessed to names and save it
+/// as an index
+struct SaveIndex<'a, N> {
+    matcher: &'a Matcher,
+    to_names: Vec<NameProcessed<N>>,
+    kept_columns: Vec<String>,
+    idf: Idf,
+    index_path: &'a str,
+}
+
+impl<N: MatchableName> ModeTask<N> for SaveIndex<'_, N> {
+    type Output = anyhow::Result<()>;
+
+    fn run<M>(self, match_mode: M) -> Self::Output
+    where
+        M: MatchMode<N> + Sync,
+        M::MatchableData: Send + Sync,
+        N: PotentialMatches<M>,
+    {
+        let SaveIndex {
+            matcher,
             to_names,
-            prep_opts,
-            match_opts,
-            tx,
-        ),
+            kept_columns,
+            idf,
+            index_path,
+        } = self;
+        let lookup = <N as PotentialMatches<M>>::to_names_weighted(&match_mode, to_names, &idf);
+
+        PrebuiltIndex {
+            grouped: matcher.group_match(),
+            preprocessing_options: matcher.preprocessing_options().clone(),
+            idf_scheme: matcher.idf_scheme(),
+            mode: matcher.mode().clone(),
+            to_columns: matcher.to_columns().clone(),
+            kept_columns,
+            idf,
+            lookup,
+        }
+        .save(index_path)
+    }
+}
+
+/// The Idf of the names given by the matcher's IDF source
+fn source_idf<N: UnprocessedName + Sync>(
+    matcher: &Matcher,
+    from_names: &[&N],
+    to_names: &[NameProcessed<N>],
+) -> Idf {
+    let prep_documents = |names: Vec<&str>| -> Vec<Vec<String>> {
+        names
+            .into_par_iter()
+            .map(|n| prep_words(n, matcher.preprocessing_options()))
+            .collect()
+    };
+    let from_documents = match matcher.idf_source() {
+        IdfSource::To => vec![],
+        IdfSource::From | IdfSource::Both => {
+            prep_documents(from_names.iter().map(|n| n.unprocessed_name()).collect())
+        }
+        IdfSource::Reference(names) => prep_documents(names.iter().map(|n| n.as_str()).collect()),
     };
+    let to_documents = match matcher.idf_source() {
+        IdfSource::To | IdfSource::Both => to_names.iter().map(|n| n.token_document()).collect(),
+        IdfSource::From | IdfSource::Reference(_) => vec![],
+    };
+
+    Idf::from_documents(
+        to_documents
+            .into_iter()
+            .chain(from_documents.iter().map(|d| d.iter().collect())),
+        matcher.idf_scheme(),
+    )
+}
+
+fn check_idf(idf: &Idf) -> anyhow::Result<()> {
+    if idf.is_empty() {
+        anyhow::bail!("the names given by --idf-source have no tokens to compute IDF weights from");
+    }
+    Ok(())
 }
 
-fn match_vec_to_generic<M, N>(
-    match_mode: M,
+/// Match every from name against the lookup of to names, stopping at the first
+/// batch that cannot be sent
+fn match_lookup<M, N>(
+    match_mode: &M,
+    matcher: &Matcher,
     from_names: Vec<N>,
-    to_names: Vec<N>,
-    prep_opts: &PreprocessingOptions,
-    match_opts: &MatchOptions,
-    send_channel: mpsc::Sender<Vec<MatchResultSend>>,
-) where
+    lookup: &N::Lookup,
+    idf: &Idf,
+    send_channel: mpsc::Sender<MatchBatch>,
+) -> Result<MatchSummary, SendError<MatchBatch>>
+where
     M: MatchMode<N> + Sync,
     M::MatchableData: Send + Sync,
     N: Sized + Send + UnprocessedName,
     N: PotentialMatches<M>,
-    <N as PotentialMatches<M>>::Lookup: Sync,
 {
-    // Create the Idf.
-    let to_names_processed = prep_names(to_names, prep_opts);
-    let idf: Idf = Idf::new(&to_names_processed);
+    let prep_opts = matcher.preprocessing_options();
+    let match_opts = matcher.match_options();
+    let fallback = *matcher.group_fallback() == GroupFallback::All;
+
+    let mut orphaned_groups: BTreeMap<String, usize> = BTreeMap::new();
+    for group in from_names.iter().filter_map(|n| n.group_key()) {
+        if !N::has_group(lookup, group) {
+            *orphaned_groups.entry(group.to_string()).or_default() += 1;
+        }
+    }
+    let summary = MatchSummary {
+        from_names: from_names.len(),
+        group_fallback: fallback,
+        orphaned_groups,
+    };
 
-    // Get the match iterator
-    let to_names_weighted = N::to_names_weighted(&match_mode, to_names_processed, &idf);
+    let progress_bar = match matcher.show_progress() {
+        true => ProgressBar::new(from_names.len() as u64),
+        false => ProgressBar::hidden(),
+    };
 
-    let _: Vec<_> = from_names
+    from_names
         .into_par_iter()
-        .progress()
-        .map_with(send_channel, |s, from_name| {
+        .enumerate()
+        .progress_with(progress_bar)
+        .try_for_each_with(send_channel, |s, (from_index, from_name)| {
             let from_name_processed = prep_name(from_name, prep_opts);
-            let from_name_weighted = match_mode.make_matchable_name(from_name_processed, &idf);
-            if let Some(to_potential_names) =
-                N::potential_matches(from_name_weighted.get_name(), &to_names_weighted)
-            {
-                let best_matches: Vec<_> = best_matches_for_single_name(
-                    &match_mode,
-                    &from_name_weighted,
-                    to_potential_names,
-                    match_opts,
-                );
-
-                let match_results_to_send: Vec<_> = best_matches
-                    .iter()
-                    .map(|bm| MatchResultSend {
+            let from_name_weighted = match_mode.make_matchable_name(from_name_processed, idf);
+            let candidates =
+                N::potential_matches(match_mode, &from_name_weighted, lookup, fallback);
+            let best_matches = best_matches_for_single_name(
+                match_mode,
+                &from_name_weighted,
+                &candidates,
+                match_opts,
+            );
+            let mut match_results_to_send: Vec<_> = best_matches
+                .iter()
+                .map(|rm| {
+                    let bm = &rm.result;
+                    // Only the best matches are explained, as explaining is costly
+                    let (searched, position) = rm.to_index;
+                    let explanation = matcher.explain().then(|| {
+                        match_mode
+                            .explain_match(&from_name_weighted, candidates[searched].get(position))
+                    });
+                    MatchResultSend {
                         from_name: bm.from_name().unprocessed_name().to_string(),
                         from_id: bm.from_name().idx().to_string(),
                         to_name: bm.to_name().unprocessed_name().to_string(),
                         to_id: bm.to_name().idx().to_string(),
-                        score: *bm.score(),
-                    })
-                    .collect();
+                        score: Some(*bm.score()),
+                        from_kept: bm.from_name().kept().to_vec(),
+                        to_kept: bm.to_name().kept().to_vec(),
+                        explanation,
+                    }
+                })
+                .collect();
 
-                s.send(match_results_to_send).unwrap();
+            if match_results_to_send.is_empty() && match_opts.include_unmatched {
+                match_results_to_send
+                    .push(MatchResultSend::unmatched(from_name_weighted.get_name()));
             }
-        })
-        .collect();
+
+            s.send(MatchBatch {
+                from_index,
+                results: match_results_to_send,
+            })
+        })?;
+
+    Ok(summary)
 }
 
 fn best_matches_for_single_name<'a, T, N>(
     match_mode: &'a T,
     from_name: &'a T::MatchableData,
-    to_names: &'a [T::MatchableData],
+    to_names: &[Candidates<'a, T::MatchableData>],
     match_opts: &MatchOptions,
-) -> Vec<MatchResult<'a, N>>
+) -> Vec<RankedMatch<'a, N>>
 where
     T: MatchMode<N>,
 {
     let best_matches: MinMaxTieHeap<_> = to_names
         .iter()
-        .filter_map(|to_name| {
+        .enumerate()
+        .flat_map(|(searched, candidates)| {
+            candidates
+                .iter_indexed()
+                .map(move |(position, to_name)| ((searched, position), to_name))
+        })
+        .filter_map(|(to_index, to_name)| {
             let match_result = match_mode.score_match(from_name, to_name);
             if match_result.score > match_opts.minimum_score {
-                Some(match_result)
+                Some(RankedMatch {
+                    result: match_result,
+                    same_legal_form: from_name.legal_form().is_some()
+                        && from_name.legal_form() == to_name.legal_form(),
+                    to_index,
+                })
             } else {
                 None
             }
@@ -197,36 +485,12 @@ type AreTiedFn<T> = dyn Fn(&T, &T) -> bool;
 
 fn min_max_tie_heap_identity_element<'a, N>(
     match_opts: &MatchOptions,
-) -> MinMaxTieHeap<MatchResult<'a, N>> {
-    let are_tied: Box<AreTiedFn<MatchResult<N>>> = match match_opts.ties_within {
-        None => Box::new(|_: &MatchResult<N>, _: &MatchResult<N>| false),
-        Some(eps) => {
-            Box::new(move |a: &MatchResult<N>, b: &MatchResult<N>| (a.score - b.score).abs() < eps)
-        }
+) -> MinMaxTieHeap<RankedMatch<'a, N>> {
+    let are_tied: Box<AreTiedFn<RankedMatch<N>>> = match match_opts.ties_within {
+        None => Box::new(|_: &RankedMatch<N>, _: &RankedMatch<N>| false),
+        Some(eps) => Box::new(move |a: &RankedMatch<N>, b: &RankedMatch<N>| {
+            (a.result.score - b.result.score).abs() < eps
+        }),
     };
     MinMaxTieHeap::new(match_opts.num_results, are_tied)
 }
-
-fn spawn_csv_writer(path: &str, rx: mpsc::Receiver<Vec<MatchResultSend>>) -> anyhow::Result<()> {
-    let output_file = OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(path)
-        .with_context(|| format!("when accessing output file {}", path))?;
-    // .map_err(|e| wrap_error(e, format!("when accessing output file {}", path)))?;
-
-    let mut wtr = WriterBuilder::new()
-        .has_headers(true)
-        .from_writer(output_file);
-
-    thread::spawn(move || {
-        while let Ok(match_results) = rx.recv() {
-            let _: Vec<_> = match_results
-                .iter()
-                .map(|mrs| wtr.serialize(mrs).unwrap())
-                .collect();
-        }
-    });
-
-    Ok(())
-}
@@ -0,0 +1,265 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::{Either, Itertools};
+use serde::{Deserialize, Serialize};
+
+use crate::core::{BkTree, EditDistance};
+
+/// The to-names that may score above zero against a single from-name.
+#[derive(Debug)]
+pub enum Candidates<'a, T> {
+    /// Every name in the lookup must be scored
+    All(&'a [T]),
+    /// Only the names at the given (sorted, unique) positions need to be scored
+    Subset(&'a [T], Vec<usize>),
+}
+
+impl<'a, T> Candidates<'a, T> {
+    pub fn iter(&self) -> impl Iterator<Item = &'a T> + '_ {
+        match self {
+            Candidates::All(names) => Either::Left(names.iter()),
+            Candidates::Subset(names, positions) => {
+                Either::Right(positions.iter().map(move |&i| &names[i]))
+            }
+        }
+    }
+
+    /// The candidates along with their positions in the lookup, which follow the
+    /// order the names were indexed in
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &'a T)> + '_ {
+        match self {
+            Candidates::All(names) => Either::Left(names.iter().enumerate()),
+            Candidates::Subset(names, positions) => {
+                Either::Right(positions.iter().map(move |&i| (i, &names[i])))
+            }
+        }
+    }
+
+    /// The name at `position` in the lookup, as given by `iter_indexed`
+    pub fn get(&self, position: usize) -> &'a T {
+        match self {
+            Candidates::All(names) | Candidates::Subset(names, _) => &names[position],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Candidates::All(names) => names.len(),
+            Candidates::Subset(_, positions) => positions.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An inverted index from a key, such as a token, to the position of every name
+/// containing that key.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvertedIndex<T> {
+    names: Vec<T>,
+    postings: HashMap<String, Vec<usize>>,
+}
+
+impl<T> InvertedIndex<T> {
+    /// Build the index using `keys` to list the keys of each name. Keys may be
+    /// repeated for a name.
+    pub fn new<F>(names: Vec<T>, keys: F) -> Self
+    where
+        F: Fn(&T) -> Vec<&String>,
+    {
+        let mut postings: HashMap<String, Vec<usize>> = HashMap::new();
+
+        for (i, name) in names.iter().enumerate() {
+            for key in keys(name) {
+                let positions = postings.entry(key.to_string()).or_default();
+                if positions.last() != Some(&i) {
+                    positions.push(i);
+                }
+            }
+        }
+
+        InvertedIndex { names, postings }
+    }
+
+    pub fn names(&self) -> &[T] {
+        &self.names
+    }
+
+    /// The positions of every name sharing at least one key with `keys`
+    pub fn candidates<'k, I>(&self, keys: I) -> Candidates<'_, T>
+    where
+        I: IntoIterator<Item = &'k String>,
+    {
+        let mut positions: Vec<usize> = keys
+            .into_iter()
+            .filter_map(|k| self.postings.get(k))
+            .flatten()
+            .copied()
+            .collect();
+        positions.sort_unstable();
+        positions.dedup();
+
+        Candidates::Subset(&self.names, positions)
+    }
+
+    /// The positions of every name sharing at least `min_shared` distinct keys
+    /// with `keys`
+    pub fn candidates_sharing<'k, I>(&self, keys: I, min_shared: usize) -> Candidates<'_, T>
+    where
+        I: IntoIterator<Item = &'k String>,
+    {
+        let unique_keys: HashSet<&String> = keys.into_iter().collect();
+        let mut positions: Vec<usize> = unique_keys
+            .into_iter()
+            .filter_map(|k| self.postings.get(k))
+            .flatten()
+            .copied()
+            .collect();
+        positions.sort_unstable();
+
+        let positions = positions
+            .into_iter()
+            .group_by(|&i| i)
+            .into_iter()
+            .filter_map(|(i, shared)| match shared.count() >= min_shared {
+                true => Some(i),
+                false => None,
+            })
+            .collect();
+
+        Candidates::Subset(&self.names, positions)
+    }
+}
+
+/// An index over the token vocabulary of the names, returning names with a token
+/// within an edit distance of some from token. Without a maximum distance, every
+/// name is a candidate.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EditDistanceIndex<T> {
+    tokens: InvertedIndex<T>,
+    vocabulary: BkTree,
+    max_distance: Option<usize>,
+}
+
+impl<T> EditDistanceIndex<T> {
+    pub fn new<F>(
+        names: Vec<T>,
+        tokens: F,
+        distance: EditDistance,
+        max_distance: Option<usize>,
+    ) -> Self
+    where
+        F: Fn(&T) -> Vec<&String>,
+    {
+        let tokens = InvertedIndex::new(names, tokens);
+        let mut vocabulary = BkTree::new(distance);
+        if max_distance.is_some() {
+            for token in tokens.postings.keys() {
+                vocabulary.insert(token);
+            }
+        }
+
+        EditDistanceIndex {
+            tokens,
+            vocabulary,
+            max_distance,
+        }
+    }
+
+    pub fn candidates<'k, I>(&self, from_tokens: I) -> Candidates<'_, T>
+    where
+        I: IntoIterator<Item = &'k String>,
+    {
+        match self.max_distance {
+            None => Candidates::All(self.tokens.names()),
+            Some(k) => {
+                let nearby_tokens: Vec<&String> = from_tokens
+                    .into_iter()
+                    .flat_map(|t| self.vocabulary.find(t, k))
+                    .collect();
+                self.tokens.candidates(nearby_tokens)
+            }
+        }
+    }
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tokens(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn inverted_index_candidates() {
+        let names = vec![
+            tokens("bank of america"),
+            tokens("apple"),
+            tokens("america online"),
+            tokens("bank bank"),
+        ];
+        let index = InvertedIndex::new(names, |n| n.iter().collect());
+
+        let from = tokens("america bank");
+        let found: Vec<_> = index.candidates(&from).iter().cloned().collect();
+        assert_eq!(
+            found,
+            vec![
+                tokens("bank of america"),
+                tokens("america online"),
+                tokens("bank bank"),
+            ]
+        );
+
+        let from = tokens("microsoft");
+        assert!(index.candidates(&from).is_empty());
+    }
+
+    #[test]
+    fn inverted_index_candidates_sharing() {
+        let names = vec![
+            tokens("bank of america"),
+            tokens("apple"),
+            tokens("america online"),
+            tokens("bank bank"),
+        ];
+        let index = InvertedIndex::new(names, |n| n.iter().collect());
+
+        let from = tokens("america bank bank of");
+        let found: Vec<_> = index.candidates_sharing(&from, 2).iter().cloned().collect();
+        assert_eq!(found, vec![tokens("bank of america")]);
+
+        let found = index.candidates_sharing(&from, 1);
+        assert_eq!(found.len(), 3);
+    }
+
+    #[test]
+    fn edit_distance_candidates() {
+        let names = vec![tokens("jon smyth"), tokens("jane doe"), tokens("smith")];
+        let from = tokens("john");
+
+        let index = EditDistanceIndex::new(
+            names,
+            |n| n.iter().collect(),
+            EditDistance::Levenshtein,
+            Some(1),
+        );
+        let found: Vec<_> = index.candidates(&from).iter().cloned().collect();
+        assert_eq!(found, vec![tokens("jon smyth")]);
+
+        let names = vec![tokens("jon smyth"), tokens("jane doe"), tokens("smith")];
+        let index = EditDistanceIndex::new(
+            names,
+            |n| n.iter().collect(),
+            EditDistance::Levenshtein,
+            None,
+        );
+        assert_eq!(index.candidates(&from).len(), 3);
+    }
+}
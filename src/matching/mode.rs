@@ -2,12 +2,16 @@ use std::collections::BTreeMap;
 use std::marker::Send;
 
 use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
-use crate::core::Idf;
-use crate::matching::MatchResult;
+use crate::core::{EditDistance, Idf};
+use crate::matching::index::{Candidates, EditDistanceIndex, InvertedIndex};
+use crate::matching::result::MatchResult;
 use crate::name::{NameContainer, NameGrouped, NameUngrouped};
 use crate::name::{
-    NameDamerauLevenshtein, NameLevenshtein, NameNGrams, NameProcessed, NameWeighted,
+    NameDamerauLevenshtein, NameJaroWinkler, NameLevenshtein, NameNGrams, NamePerson,
+    NameProcessed, NameWeighted, ScoreExplanation,
 };
 
 /******************************************************************************/
@@ -15,6 +19,8 @@ use crate::name::{
 /******************************************************************************/
 pub trait MatchMode<N> {
     type MatchableData: NameContainer<N>;
+    /// A lookup structure over to-names used to generate candidate matches
+    type Index;
 
     fn make_matchable_name(&self, np: NameProcessed<N>, idf: &Idf) -> Self::MatchableData;
     fn score_match<'a>(
@@ -22,6 +28,15 @@ pub trait MatchMode<N> {
         _: &'a Self::MatchableData,
         _: &'a Self::MatchableData,
     ) -> MatchResult<'a, N>;
+    /// The token pairs, weights and norms behind the score of `score_match`
+    fn explain_match(&self, _: &Self::MatchableData, _: &Self::MatchableData) -> ScoreExplanation;
+    fn build_index(&self, names: Vec<Self::MatchableData>) -> Self::Index;
+    /// The names in the index that may score above zero against the from name
+    fn candidates<'a>(
+        &self,
+        from_name: &Self::MatchableData,
+        index: &'a Self::Index,
+    ) -> Candidates<'a, Self::MatchableData>;
 }
 
 pub trait PotentialMatches<M>
@@ -29,14 +44,22 @@ where
     M: MatchMode<Self>,
     Self: Sized,
 {
-    type Lookup;
+    type Lookup: Sync + Serialize + DeserializeOwned;
 
     fn to_names_weighted(match_mode: &M, ns: Vec<NameProcessed<Self>>, idf: &Idf) -> Self::Lookup;
 
+    /// Whether the lookup holds to names of the group
+    fn has_group(pml: &Self::Lookup, group: &str) -> bool;
+
+    /// The candidates for the from name, drawn from one index, or from several
+    /// when `fallback` lets a grouped name whose group has no to names search
+    /// every group. No index at all leaves the name unmatched.
     fn potential_matches<'a>(
-        n: &'a Self,
+        match_mode: &M,
+        n: &M::MatchableData,
         pml: &'a Self::Lookup,
-    ) -> Option<&'a Vec<M::MatchableData>>;
+        fallback: bool,
+    ) -> Vec<Candidates<'a, M::MatchableData>>;
 }
 
 /******************************************************************************/
@@ -47,6 +70,7 @@ pub struct TokenMatch;
 
 impl<N> MatchMode<N> for TokenMatch {
     type MatchableData = NameWeighted<N>;
+    type Index = InvertedIndex<NameWeighted<N>>;
 
     fn make_matchable_name(&self, np: NameProcessed<N>, idf: &Idf) -> Self::MatchableData {
         NameWeighted::new(np, idf)
@@ -63,24 +87,58 @@ impl<N> MatchMode<N> for TokenMatch {
             score: from_name_weighted.compute_match_score(to_name_weighted),
         }
     }
+
+    fn explain_match(
+        &self,
+        from_name_weighted: &Self::MatchableData,
+        to_name_weighted: &Self::MatchableData,
+    ) -> ScoreExplanation {
+        from_name_weighted.explain_match_score(to_name_weighted)
+    }
+
+    fn build_index(&self, names: Vec<Self::MatchableData>) -> Self::Index {
+        InvertedIndex::new(names, |n| n.token_count_weights().keys().collect())
+    }
+
+    fn candidates<'a>(
+        &self,
+        from_name: &Self::MatchableData,
+        index: &'a Self::Index,
+    ) -> Candidates<'a, Self::MatchableData> {
+        index.candidates(from_name.token_count_weights().keys())
+    }
 }
 
 /******************************************************************************/
 /* Ngram match                                                                */
 /******************************************************************************/
 #[derive(Debug)]
-pub struct NGramMatch(usize);
+pub struct NGramMatch {
+    n_gram_length: usize,
+    min_shared_ngrams: usize,
+}
 
 impl NGramMatch {
     pub fn new(n: usize) -> Self {
-        NGramMatch(n)
+        NGramMatch {
+            n_gram_length: n,
+            min_shared_ngrams: 1,
+        }
+    }
+
+    /// Only score names sharing at least `k` distinct n-grams with the from name.
+    /// Values above one may exclude pairs with a positive score.
+    pub fn with_min_shared_ngrams(mut self, k: usize) -> Self {
+        self.min_shared_ngrams = k;
+        self
     }
 }
 impl<N> MatchMode<N> for NGramMatch {
     type MatchableData = NameNGrams<N>;
+    type Index = InvertedIndex<NameNGrams<N>>;
 
     fn make_matchable_name(&self, np: NameProcessed<N>, idf: &Idf) -> Self::MatchableData {
-        NameNGrams::new(np, idf, self.0)
+        NameNGrams::new(np, idf, self.n_gram_length)
     }
 
     fn score_match<'a>(
@@ -94,16 +152,47 @@ impl<N> MatchMode<N> for NGramMatch {
             score: from_name_ngram.compute_match_score(to_name_ngram),
         }
     }
+
+    fn explain_match(
+        &self,
+        from_name_ngram: &Self::MatchableData,
+        to_name_ngram: &Self::MatchableData,
+    ) -> ScoreExplanation {
+        from_name_ngram.explain_match_score(to_name_ngram)
+    }
+
+    fn build_index(&self, names: Vec<Self::MatchableData>) -> Self::Index {
+        InvertedIndex::new(names, |n| n.ngrams())
+    }
+
+    fn candidates<'a>(
+        &self,
+        from_name: &Self::MatchableData,
+        index: &'a Self::Index,
+    ) -> Candidates<'a, Self::MatchableData> {
+        index.candidates_sharing(from_name.ngrams(), self.min_shared_ngrams)
+    }
 }
 
 /******************************************************************************/
 /* Levenshtein match                                                          */
 /******************************************************************************/
 #[derive(Debug)]
-pub struct LevenshteinMatch;
+pub struct LevenshteinMatch {
+    max_edit_distance: Option<usize>,
+}
+
+impl LevenshteinMatch {
+    /// Only score names with a token within `max_edit_distance` Levenshtein edits of a
+    /// from token. Without a maximum, every name is scored.
+    pub fn new(max_edit_distance: Option<usize>) -> Self {
+        LevenshteinMatch { max_edit_distance }
+    }
+}
 
 impl<N> MatchMode<N> for LevenshteinMatch {
     type MatchableData = NameLevenshtein<N>;
+    type Index = EditDistanceIndex<NameLevenshtein<N>>;
 
     fn make_matchable_name(&self, np: NameProcessed<N>, idf: &Idf) -> Self::MatchableData {
         NameLevenshtein::new(np, idf)
@@ -120,16 +209,52 @@ impl<N> MatchMode<N> for LevenshteinMatch {
             score: from_name_ngram.compute_match_score(to_name_ngram),
         }
     }
+
+    fn explain_match(
+        &self,
+        from_name_ngram: &Self::MatchableData,
+        to_name_ngram: &Self::MatchableData,
+    ) -> ScoreExplanation {
+        from_name_ngram.explain_match_score(to_name_ngram)
+    }
+
+    fn build_index(&self, names: Vec<Self::MatchableData>) -> Self::Index {
+        EditDistanceIndex::new(
+            names,
+            |n| n.token_weights().iter().map(|(t, _)| t).collect(),
+            EditDistance::Levenshtein,
+            self.max_edit_distance,
+        )
+    }
+
+    fn candidates<'a>(
+        &self,
+        from_name: &Self::MatchableData,
+        index: &'a Self::Index,
+    ) -> Candidates<'a, Self::MatchableData> {
+        index.candidates(from_name.token_weights().iter().map(|(t, _)| t))
+    }
 }
 
 /******************************************************************************/
 /* Damerau-Levenshtein match                                                  */
 /******************************************************************************/
 #[derive(Debug)]
-pub struct DamerauLevenshteinMatch;
+pub struct DamerauLevenshteinMatch {
+    max_edit_distance: Option<usize>,
+}
+
+impl DamerauLevenshteinMatch {
+    /// Only score names with a token within `max_edit_distance` Damerau-Levenshtein edits of a
+    /// from token. Without a maximum, every name is scored.
+    pub fn new(max_edit_distance: Option<usize>) -> Self {
+        DamerauLevenshteinMatch { max_edit_distance }
+    }
+}
 
 impl<N> MatchMode<N> for DamerauLevenshteinMatch {
     type MatchableData = NameDamerauLevenshtein<N>;
+    type Index = EditDistanceIndex<NameDamerauLevenshtein<N>>;
 
     fn make_matchable_name(&self, np: NameProcessed<N>, idf: &Idf) -> Self::MatchableData {
         NameDamerauLevenshtein::new(np, idf)
@@ -146,25 +271,174 @@ impl<N> MatchMode<N> for DamerauLevenshteinMatch {
             score: from_name_ngram.compute_match_score(to_name_ngram),
         }
     }
+
+    fn explain_match(
+        &self,
+        from_name_ngram: &Self::MatchableData,
+        to_name_ngram: &Self::MatchableData,
+    ) -> ScoreExplanation {
+        from_name_ngram.explain_match_score(to_name_ngram)
+    }
+
+    fn build_index(&self, names: Vec<Self::MatchableData>) -> Self::Index {
+        EditDistanceIndex::new(
+            names,
+            |n| n.token_weights().iter().map(|(t, _)| t).collect(),
+            EditDistance::DamerauLevenshtein,
+            self.max_edit_distance,
+        )
+    }
+
+    fn candidates<'a>(
+        &self,
+        from_name: &Self::MatchableData,
+        index: &'a Self::Index,
+    ) -> Candidates<'a, Self::MatchableData> {
+        index.candidates(from_name.token_weights().iter().map(|(t, _)| t))
+    }
+}
+/******************************************************************************/
+/* Jaro-Winkler match                                                         */
+/******************************************************************************/
+#[derive(Debug)]
+pub struct JaroWinklerMatch {
+    prefix_scale: f64,
+}
+
+impl JaroWinklerMatch {
+    /// The `prefix_scale` rewards each character of common prefix, up to four, and
+    /// should not exceed 0.25.
+    pub fn new(prefix_scale: f64) -> Self {
+        JaroWinklerMatch { prefix_scale }
+    }
+}
+
+impl<N> MatchMode<N> for JaroWinklerMatch {
+    type MatchableData = NameJaroWinkler<N>;
+    type Index = Vec<NameJaroWinkler<N>>;
+
+    fn make_matchable_name(&self, np: NameProcessed<N>, idf: &Idf) -> Self::MatchableData {
+        NameJaroWinkler::new(np, idf)
+    }
+
+    fn score_match<'a>(
+        &self,
+        from_name_jw: &'a Self::MatchableData,
+        to_name_jw: &'a Self::MatchableData,
+    ) -> MatchResult<'a, N> {
+        MatchResult {
+            from_name: from_name_jw.name(),
+            to_name: to_name_jw.name(),
+            score: from_name_jw.compute_match_score(to_name_jw, self.prefix_scale),
+        }
+    }
+
+    fn explain_match(
+        &self,
+        from_name_jw: &Self::MatchableData,
+        to_name_jw: &Self::MatchableData,
+    ) -> ScoreExplanation {
+        from_name_jw.explain_match_score(to_name_jw, self.prefix_scale)
+    }
+
+    fn build_index(&self, names: Vec<Self::MatchableData>) -> Self::Index {
+        names
+    }
+
+    fn candidates<'a>(
+        &self,
+        _: &Self::MatchableData,
+        index: &'a Self::Index,
+    ) -> Candidates<'a, Self::MatchableData> {
+        Candidates::All(index)
+    }
+}
+
+/******************************************************************************/
+/* Person match                                                               */
+/******************************************************************************/
+#[derive(Debug)]
+pub struct PersonMatch {
+    initial_similarity: f64,
+}
+
+impl PersonMatch {
+    /// An initial matching a name part scores `1 - initial_discount` as a pair.
+    pub fn new(initial_discount: f64) -> Self {
+        PersonMatch {
+            initial_similarity: 1.0 - initial_discount,
+        }
+    }
 }
+
+impl<N> MatchMode<N> for PersonMatch {
+    type MatchableData = NamePerson<N>;
+    type Index = InvertedIndex<NamePerson<N>>;
+
+    fn make_matchable_name(&self, np: NameProcessed<N>, idf: &Idf) -> Self::MatchableData {
+        NamePerson::new(np, idf)
+    }
+
+    fn score_match<'a>(
+        &self,
+        from_name_person: &'a Self::MatchableData,
+        to_name_person: &'a Self::MatchableData,
+    ) -> MatchResult<'a, N> {
+        MatchResult {
+            from_name: from_name_person.name(),
+            to_name: to_name_person.name(),
+            score: from_name_person.compute_match_score(to_name_person, self.initial_similarity),
+        }
+    }
+
+    fn explain_match(
+        &self,
+        from_name_person: &Self::MatchableData,
+        to_name_person: &Self::MatchableData,
+    ) -> ScoreExplanation {
+        from_name_person.explain_match_score(to_name_person, self.initial_similarity)
+    }
+
+    fn build_index(&self, names: Vec<Self::MatchableData>) -> Self::Index {
+        InvertedIndex::new(names, |n| n.index_keys().iter().collect())
+    }
+
+    fn candidates<'a>(
+        &self,
+        from_name: &Self::MatchableData,
+        index: &'a Self::Index,
+    ) -> Candidates<'a, Self::MatchableData> {
+        index.candidates(&from_name.lookup_keys())
+    }
+}
+
 impl<M> PotentialMatches<M> for NameUngrouped
 where
     M: MatchMode<NameUngrouped> + Sync + Sized,
     M::MatchableData: Send + Sync,
+    M::Index: Sync + Serialize + DeserializeOwned,
 {
-    type Lookup = Vec<M::MatchableData>;
+    type Lookup = M::Index;
 
     fn to_names_weighted(match_mode: &M, ns: Vec<NameProcessed<Self>>, idf: &Idf) -> Self::Lookup {
-        ns.into_par_iter()
+        let names = ns
+            .into_par_iter()
             .map(|name_processed| match_mode.make_matchable_name(name_processed, idf))
-            .collect()
+            .collect();
+        match_mode.build_index(names)
+    }
+
+    fn has_group(_: &Self::Lookup, _: &str) -> bool {
+        false
     }
 
     fn potential_matches<'a>(
-        _: &'a Self,
+        match_mode: &M,
+        n: &M::MatchableData,
         pml: &'a Self::Lookup,
-    ) -> Option<&'a Vec<M::MatchableData>> {
-        Some(pml)
+        _: bool,
+    ) -> Vec<Candidates<'a, M::MatchableData>> {
+        vec![match_mode.candidates(n, pml)]
     }
 }
 
@@ -172,29 +446,43 @@ impl<M> PotentialMatches<M> for NameGrouped
 where
     M: MatchMode<NameGrouped> + Sync + Sized,
     M::MatchableData: Send + Sync,
+    M::Index: Sync + Serialize + DeserializeOwned,
 {
-    type Lookup = BTreeMap<String, Vec<M::MatchableData>>;
+    type Lookup = BTreeMap<String, M::Index>;
 
     fn to_names_weighted(match_mode: &M, ns: Vec<NameProcessed<Self>>, idf: &Idf) -> Self::Lookup {
-        let mut pml: Self::Lookup = BTreeMap::new();
+        let mut groups: BTreeMap<String, Vec<M::MatchableData>> = BTreeMap::new();
 
         for name_processed in ns {
             let matchable_name = match_mode.make_matchable_name(name_processed, idf);
             let g = matchable_name.get_name().group();
-            let v = pml.entry(g.clone()).or_default();
+            let v = groups.entry(g.clone()).or_default();
             v.push(matchable_name)
         }
 
-        pml
-        // ns.into_par_iter()
-        //     .map(|name_processed| match_mode.make_matchable_name(name_processed, &idf))
-        //     .collect()
+        groups
+            .into_iter()
+            .map(|(g, names)| (g, match_mode.build_index(names)))
+            .collect()
+    }
+
+    fn has_group(pml: &Self::Lookup, group: &str) -> bool {
+        pml.contains_key(group)
     }
 
     fn potential_matches<'a>(
-        n: &'a Self,
+        match_mode: &M,
+        n: &M::MatchableData,
         pml: &'a Self::Lookup,
-    ) -> Option<&'a Vec<M::MatchableData>> {
-        pml.get(n.group())
+        fallback: bool,
+    ) -> Vec<Candidates<'a, M::MatchableData>> {
+        match pml.get(n.get_name().group()) {
+            Some(index) => vec![match_mode.candidates(n, index)],
+            None if fallback => pml
+                .values()
+                .map(|index| match_mode.candidates(n, index))
+                .collect(),
+            None => vec![],
+        }
     }
 }
@@ -0,0 +1,313 @@
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::thread;
+
+use anyhow::Context;
+use csv::WriterBuilder;
+use serde_json::{Map, Value};
+
+use crate::core::{ReorderBuffer, STDIO_PATH};
+use crate::matching::result::{MatchBatch, MatchResultSend};
+
+type Output = Box<dyn Write + Send>;
+
+/// The handle of a writer thread, which returns any error writing the results.
+/// The writer finishes once every sender is dropped.
+pub type WriterHandle = thread::JoinHandle<anyhow::Result<()>>;
+
+/// File formats for writing match results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Comma separated values
+    #[default]
+    Csv,
+    /// Tab separated values
+    Tsv,
+    /// One JSON object per line, with a null score and to fields for unmatched names
+    Jsonl,
+}
+
+impl OutputFormat {
+    pub const VARIANTS: &'static [&'static str] = &["csv", "tsv", "jsonl"];
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            _ => Err(format!(
+                "unknown output format {}, expected one of {}",
+                s,
+                OutputFormat::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Jsonl => "jsonl",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Spawn a thread writing the match results received on `rx` to a new file at
+/// `path`, or to standard output for `-`. The headers name every field, including
+/// the kept columns. Ordered output is written in from name order.
+pub fn spawn_writer(
+    path: &str,
+    format: OutputFormat,
+    headers: Vec<String>,
+    ordered: bool,
+    rx: mpsc::Receiver<MatchBatch>,
+) -> anyhow::Result<WriterHandle> {
+    let output_file: Output = match path {
+        STDIO_PATH => Box::new(io::stdout()),
+        _ => Box::new(
+            OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .with_context(|| format!("when accessing output file {}", path))?,
+        ),
+    };
+
+    match format {
+        OutputFormat::Csv => spawn_delimited_writer(path, output_file, b',', headers, ordered, rx),
+        OutputFormat::Tsv => spawn_delimited_writer(path, output_file, b'\t', headers, ordered, rx),
+        OutputFormat::Jsonl => Ok(spawn_jsonl_writer(path, output_file, headers, ordered, rx)),
+    }
+}
+
+fn spawn_delimited_writer(
+    path: &str,
+    output_file: Output,
+    delimiter: u8,
+    headers: Vec<String>,
+    ordered: bool,
+    rx: mpsc::Receiver<MatchBatch>,
+) -> anyhow::Result<WriterHandle> {
+    // Headers are written manually as the kept columns are only known at run time
+    let mut wtr = WriterBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .from_writer(output_file);
+    wtr.write_record(&headers)
+        .with_context(|| format!("when writing to output file {}", path))?;
+
+    let path = path.to_string();
+    Ok(thread::spawn(move || {
+        for match_results in received_results(rx, ordered) {
+            for mrs in &match_results {
+                // Unmatched rows have no to values or explanation, so pad the
+                // trailing columns
+                let padding = vec![""; headers.len() - mrs.width()];
+                let explanation = mrs
+                    .explanation
+                    .iter()
+                    .map(serde_json::to_string)
+                    .collect::<Result<Vec<_>, _>>()?;
+                wtr.serialize((mrs, &mrs.from_kept, &mrs.to_kept, explanation, padding))
+                    .with_context(|| format!("when writing to output file {}", path))?;
+            }
+        }
+        wtr.flush()
+            .with_context(|| format!("when writing to output file {}", path))
+    }))
+}
+
+fn spawn_jsonl_writer(
+    path: &str,
+    output_file: Output,
+    headers: Vec<String>,
+    ordered: bool,
+    rx: mpsc::Receiver<MatchBatch>,
+) -> WriterHandle {
+    let mut wtr = BufWriter::new(output_file);
+
+    let path = path.to_string();
+    thread::spawn(move || {
+        for match_results in received_results(rx, ordered) {
+            for mrs in &match_results {
+                serde_json::to_writer(&mut wtr, &json_record(&headers, mrs)?)
+                    .with_context(|| format!("when writing to output file {}", path))?;
+                writeln!(wtr).with_context(|| format!("when writing to output file {}", path))?;
+            }
+        }
+        wtr.flush()
+            .with_context(|| format!("when writing to output file {}", path))
+    })
+}
+
+/// The batches of results received on `rx`, in from name order when ordered.
+fn received_results(
+    rx: mpsc::Receiver<MatchBatch>,
+    ordered: bool,
+) -> Box<dyn Iterator<Item = Vec<MatchResultSend>>> {
+    match ordered {
+        true => {
+            let mut buffer = ReorderBuffer::new();
+            Box::new(
+                rx.into_iter()
+                    .flat_map(move |b| buffer.push(b.from_index, b.results)),
+            )
+        }
+        false => Box::new(rx.into_iter().map(|b| b.results)),
+    }
+}
+
+/// A JSON object keyed by the headers. The to fields of an unmatched result, its
+/// kept to columns and its explanation are null.
+fn json_record(
+    headers: &[String],
+    mrs: &MatchResultSend,
+) -> serde_json::Result<Map<String, Value>> {
+    let matched = mrs.score.is_some();
+    let to_value = |s: &String| match matched {
+        true => Value::from(s.as_str()),
+        false => Value::Null,
+    };
+
+    let values = vec![
+        Value::from(mrs.from_name.as_str()),
+        Value::from(mrs.from_id.as_str()),
+        to_value(&mrs.to_name),
+        to_value(&mrs.to_id),
+        mrs.score.map_or(Value::Null, Value::from),
+    ]
+    .into_iter()
+    .chain(mrs.from_kept.iter().map(|v| Value::from(v.as_str())))
+    .chain(mrs.to_kept.iter().map(|v| Value::from(v.as_str())))
+    .chain(
+        mrs.explanation
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?,
+    )
+    .chain(std::iter::repeat(Value::Null));
+
+    Ok(headers.iter().cloned().zip(values).collect())
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::TempFile;
+
+    #[test]
+    fn json_records() {
+        let headers = MatchResultSend::headers(&["sic".to_string()], &["state".to_string()]);
+        let mrs = MatchResultSend {
+            from_name: "Psych Inc".to_string(),
+            from_id: "1".to_string(),
+            to_name: "PSYCH".to_string(),
+            to_id: "a".to_string(),
+            score: Some(0.5),
+            from_kept: vec!["7381".to_string()],
+            to_kept: vec!["CA".to_string()],
+            explanation: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&json_record(&headers, &mrs).unwrap()).unwrap(),
+            r#"{"from_name":"Psych Inc","from_id":"1","to_name":"PSYCH","to_id":"a","score":0.5,"from_sic":"7381","to_state":"CA"}"#
+        );
+
+        let unmatched = MatchResultSend {
+            score: None,
+            to_kept: vec![],
+            ..mrs
+        };
+        assert_eq!(
+            serde_json::to_string(&json_record(&headers, &unmatched).unwrap()).unwrap(),
+            r#"{"from_name":"Psych Inc","from_id":"1","to_name":null,"to_id":null,"score":null,"from_sic":"7381","to_state":null}"#
+        );
+    }
+
+    #[test]
+    fn writer_completes_before_join() {
+        let output = TempFile::unwritten("output");
+        let (tx, rx) = mpsc::channel();
+        let writer = spawn_writer(
+            output.path(),
+            OutputFormat::Csv,
+            MatchResultSend::headers(&[], &[]),
+            false,
+            rx,
+        )
+        .unwrap();
+
+        for i in 0..1000 {
+            let mrs = MatchResultSend {
+                from_name: format!("name {}", i),
+                from_id: i.to_string(),
+                to_name: String::new(),
+                to_id: String::new(),
+                score: None,
+                from_kept: vec![],
+                to_kept: vec![],
+                explanation: None,
+            };
+            tx.send(MatchBatch {
+                from_index: i,
+                results: vec![mrs],
+            })
+            .unwrap();
+        }
+        drop(tx);
+        writer.join().unwrap().unwrap();
+
+        let contents = output.contents();
+        assert_eq!(contents.lines().count(), 1001);
+        assert!(contents.ends_with("name 999,999,,,\n"));
+    }
+
+    #[test]
+    fn ordered_writer() {
+        let output = TempFile::unwritten("ordered");
+        let (tx, rx) = mpsc::channel();
+        let headers = MatchResultSend::headers(&[], &[]);
+        let writer = spawn_writer(output.path(), OutputFormat::Csv, headers, true, rx).unwrap();
+
+        // Batches sent out of order, including an empty batch
+        for i in [2, 0, 3, 1] {
+            let results = match i {
+                3 => vec![],
+                _ => vec![MatchResultSend {
+                    from_name: format!("name {}", i),
+                    from_id: i.to_string(),
+                    to_name: String::new(),
+                    to_id: String::new(),
+                    score: None,
+                    from_kept: vec![],
+                    to_kept: vec![],
+                    explanation: None,
+                }],
+            };
+            tx.send(MatchBatch {
+                from_index: i,
+                results,
+            })
+            .unwrap();
+        }
+        drop(tx);
+        writer.join().unwrap().unwrap();
+
+        let contents = output.contents();
+        let from_ids: Vec<_> = contents.lines().skip(1).map(|l| &l[7..8]).collect();
+        assert_eq!(from_ids, vec!["0", "1", "2"]);
+    }
+}
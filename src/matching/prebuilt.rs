@@ -0,0 +1,314 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::mem;
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::PreprocessingOptions;
+use crate::core::{Idf, IdfScheme};
+use crate::matcher::{MatchModeKind, Matcher};
+use crate::name::NameColumns;
+
+/// The bytes starting every index file
+const INDEX_MAGIC: &[u8] = b"yenta-index\n";
+/// The version of the layout of index files
+const INDEX_VERSION: u32 = 3;
+
+/// The settings an index was built with, written ahead of the lookup
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexHeader {
+    version: u32,
+    grouped: bool,
+    preprocessing_options: PreprocessingOptions,
+    idf_scheme: IdfScheme,
+    mode: MatchModeKind,
+    to_columns: NameColumns,
+    kept_columns: Vec<String>,
+}
+
+/// The weighted lookup of one match mode over the to names, saved with its Idf
+/// so that later matches can skip reading, preprocessing, weighting and indexing
+/// the to file. An index can only be used with the mode, lookup settings such as
+/// the n-gram size, columns and preprocessing options it was built with.
+#[derive(Debug)]
+pub struct PrebuiltIndex<L> {
+    pub grouped: bool,
+    pub preprocessing_options: PreprocessingOptions,
+    pub idf_scheme: IdfScheme,
+    pub mode: MatchModeKind,
+    pub to_columns: NameColumns,
+    pub kept_columns: Vec<String>,
+    pub idf: Idf,
+    pub lookup: L,
+}
+
+impl<L> PrebuiltIndex<L>
+where
+    L: Serialize + DeserializeOwned,
+{
+    /// Write the index to a new file at `path`
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        if path == "-" {
+            anyhow::bail!("an index must be saved to a file, not standard output");
+        }
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .with_context(|| format!("when accessing index file {}", path))?;
+        let mut wtr = BufWriter::new(file);
+
+        let header = IndexHeader {
+            version: INDEX_VERSION,
+            grouped: self.grouped,
+            preprocessing_options: self.preprocessing_options.clone(),
+            idf_scheme: self.idf_scheme,
+            mode: self.mode.clone(),
+            to_columns: self.to_columns.clone(),
+            kept_columns: self.kept_columns.clone(),
+        };
+        wtr.write_all(INDEX_MAGIC)
+            .with_context(|| format!("when writing to index file {}", path))?;
+        bincode::serialize_into(&mut wtr, &header)
+            .and_then(|_| bincode::serialize_into(&mut wtr, &self.idf))
+            .and_then(|_| bincode::serialize_into(&mut wtr, &self.lookup))
+            .with_context(|| format!("when writing to index file {}", path))?;
+        wtr.flush()
+            .with_context(|| format!("when writing to index file {}", path))
+    }
+
+    /// Read an index written by `save`, which must suit the matcher: it must be
+    /// grouped exactly when the matcher is, and built with the same preprocessing
+    /// options, IDF scheme, to columns, match mode and lookup settings.
+    pub fn load(path: &str, matcher: &Matcher) -> anyhow::Result<Self> {
+        if path == "-" {
+            anyhow::bail!("an index must be read from a file, not standard input");
+        }
+        let file = File::open(path).with_context(|| format!("accessing {}", path))?;
+        let mut rdr = BufReader::new(file);
+
+        let mut magic = vec![0; INDEX_MAGIC.len()];
+        if rdr.read_exact(&mut magic).is_err() || magic != INDEX_MAGIC {
+            anyhow::bail!("{} is not a yenta index", path);
+        }
+        let header: IndexHeader = bincode::deserialize_from(&mut rdr)
+            .with_context(|| format!("reading the header of {}", path))?;
+        if header.version != INDEX_VERSION {
+            anyhow::bail!(
+                "{} was built by an incompatible version of yenta and must be rebuilt",
+                path
+            );
+        }
+        if header.grouped != matcher.group_match() {
+            anyhow::bail!(
+                "{} was built {} --group-match, which must also be used when matching",
+                path,
+                if header.grouped { "with" } else { "without" }
+            );
+        }
+        if &header.preprocessing_options != matcher.preprocessing_options() {
+            anyhow::bail!(
+                "{} was built with different preprocessing options, which must also be used when matching",
+                path
+            );
+        }
+        if header.idf_scheme != matcher.idf_scheme() {
+            anyhow::bail!("{} was built with --idf-scheme={}", path, header.idf_scheme);
+        }
+        if &header.to_columns != matcher.to_columns() {
+            anyhow::bail!(
+                "{} was built with different to name, id, group or kept columns, which must also be given when matching",
+                path
+            );
+        }
+        check_lookup_settings(path, &header.mode, matcher.mode())?;
+
+        let idf = bincode::deserialize_from(&mut rdr)
+            .with_context(|| format!("reading the IDF from {}", path))?;
+        let lookup = bincode::deserialize_from(&mut rdr)
+            .with_context(|| format!("reading the lookup from {}", path))?;
+
+        Ok(PrebuiltIndex {
+            grouped: header.grouped,
+            preprocessing_options: header.preprocessing_options,
+            idf_scheme: header.idf_scheme,
+            mode: header.mode,
+            to_columns: header.to_columns,
+            kept_columns: header.kept_columns,
+            idf,
+            lookup,
+        })
+    }
+}
+
+/// Check that matching in `mode` can use a lookup built for `built`: the modes
+/// must agree, along with the settings that shape the lookup. Settings that only
+/// affect scoring, such as the Jaro-Winkler prefix scale, may differ.
+fn check_lookup_settings(
+    path: &str,
+    built: &MatchModeKind,
+    mode: &MatchModeKind,
+) -> anyhow::Result<()> {
+    if mem::discriminant(built) != mem::discriminant(mode) {
+        anyhow::bail!(
+            "{} was built for --mode={}, which must also be used when matching",
+            path,
+            built
+        );
+    }
+    match lookup_settings(built) {
+        Some(settings) if lookup_settings(mode).as_ref() != Some(&settings) => {
+            anyhow::bail!("{} was built {}", path, settings)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The options of a mode that shape its lookup, as given to `index build`
+fn lookup_settings(mode: &MatchModeKind) -> Option<String> {
+    match mode {
+        MatchModeKind::NGram { n_gram_length, .. } => {
+            Some(format!("with --ngram-size={}", n_gram_length))
+        }
+        MatchModeKind::Levenshtein { max_edit_distance }
+        | MatchModeKind::DamerauLevenshtein { max_edit_distance } => match max_edit_distance {
+            Some(k) => Some(format!("with --max-edit-distance={}", k)),
+            None => Some("without --max-edit-distance".to_string()),
+        },
+        MatchModeKind::Token | MatchModeKind::JaroWinkler { .. } | MatchModeKind::Person { .. } => {
+            None
+        }
+    }
+}
+
+/// A summary of an index build
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexSummary {
+    /// The number of names indexed
+    pub names: usize,
+    /// The number of distinct tokens among the names
+    pub tokens: usize,
+}
+
+impl fmt::Display for IndexSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Indexed {} to names with {} distinct tokens",
+            self.names, self.tokens
+        )
+    }
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::matcher::MatcherBuilder;
+    use crate::matching::index::InvertedIndex;
+    use crate::matching::mode::{MatchMode, NGramMatch, PotentialMatches};
+    use crate::name::{NameContainer, NameNGrams, NameProcessed, NameUngrouped};
+    use crate::testing::TempFile;
+
+    type NGramLookup = InvertedIndex<NameNGrams<NameUngrouped>>;
+
+    #[test]
+    fn saved_index_round_trip() {
+        let file = TempFile::unwritten("index");
+        let path = file.path();
+        let names = vec![NameProcessed::new(
+            NameUngrouped::new("Psych Inc".to_string(), "1".to_string()),
+            vec!["psych".to_string(), "inc".to_string(), "inc".to_string()],
+        )];
+        let mode = MatchModeKind::NGram {
+            n_gram_length: 3,
+            min_shared_ngrams: 1,
+        };
+        let idf = Idf::new(&names);
+        let match_mode = NGramMatch::new(3);
+        let index = PrebuiltIndex {
+            grouped: false,
+            preprocessing_options: PreprocessingOptions::default(),
+            idf_scheme: IdfScheme::Smooth,
+            mode: mode.clone(),
+            to_columns: NameColumns::default(),
+            kept_columns: vec!["sic".to_string()],
+            lookup: NameUngrouped::to_names_weighted(&match_mode, names, &idf),
+            idf,
+        };
+        index.save(path).unwrap();
+
+        let matcher = Matcher::builder()
+            .mode(mode)
+            .idf_scheme(IdfScheme::Smooth)
+            .build()
+            .unwrap();
+        let loaded = PrebuiltIndex::<NGramLookup>::load(path, &matcher).unwrap();
+        assert_eq!(loaded.kept_columns, vec!["sic"]);
+        assert_eq!(loaded.lookup.names()[0].get_name().idx(), "1");
+        assert_eq!(
+            loaded.lookup.names()[0].token_counter()[&"inc".to_string()],
+            2
+        );
+
+        // The saved lookup finds the to name
+        let from_name = match_mode.make_matchable_name(
+            NameProcessed::new(
+                NameUngrouped::new("Psyche".to_string(), "a".to_string()),
+                vec!["psyche".to_string()],
+            ),
+            &loaded.idf,
+        );
+        assert_eq!(match_mode.candidates(&from_name, &loaded.lookup).len(), 1);
+
+        // Matching must use the settings the index was built with
+        let load_with = |matcher: MatcherBuilder| {
+            PrebuiltIndex::<NGramLookup>::load(path, &matcher.build().unwrap())
+                .unwrap_err()
+                .to_string()
+        };
+        let ngram = |n_gram_length| {
+            Matcher::builder()
+                .idf_scheme(IdfScheme::Smooth)
+                .mode(MatchModeKind::NGram {
+                    n_gram_length,
+                    min_shared_ngrams: 1,
+                })
+        };
+        assert!(load_with(Matcher::builder().mode(matcher.mode().clone()))
+            .contains("--idf-scheme=smooth"));
+        assert!(load_with(ngram(3).group_match(true)).contains("--group-match"));
+        assert!(
+            load_with(Matcher::builder().idf_scheme(IdfScheme::Smooth)).contains("--mode=ngram")
+        );
+        assert!(load_with(ngram(2)).contains("--ngram-size=3"));
+        let to_columns = NameColumns {
+            name: "firm".to_string(),
+            ..NameColumns::default()
+        };
+        assert!(load_with(ngram(3).to_columns(to_columns)).contains("to name, id"));
+
+        // Lookup settings that only affect scoring may differ
+        assert!(PrebuiltIndex::<NGramLookup>::load(
+            path,
+            &ngram(3)
+                .mode(MatchModeKind::NGram {
+                    n_gram_length: 3,
+                    min_shared_ngrams: 2,
+                })
+                .build()
+                .unwrap()
+        )
+        .is_ok());
+
+        assert!(index.save("Cargo.toml").is_err());
+        let error = index.save("-").unwrap_err().to_string();
+        assert!(error.contains("not standard output"));
+        assert!(!std::path::Path::new("-").exists());
+    }
+}
@@ -3,6 +3,8 @@ use std::cmp::Ordering;
 use getset::Getters;
 use serde::Serialize;
 
+use crate::name::{ScoreExplanation, UnprocessedName};
+
 /// MatchResult is an compatible with MinMaxTieHeap for storing match results.
 #[derive(Debug, Getters)]
 pub struct MatchResult<'a, N> {
@@ -16,26 +18,128 @@ pub struct MatchResult<'a, N> {
 
 impl<'a, N> PartialEq for MatchResult<'a, N> {
     fn eq(&self, other: &Self) -> bool {
-        self.score.eq(&other.score)
+        self.cmp(other) == Ordering::Equal
     }
 }
 impl<'a, N> Eq for MatchResult<'a, N> {}
 impl<'a, N> PartialOrd for MatchResult<'a, N> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.score.partial_cmp(&other.score)
+        Some(self.cmp(other))
     }
 }
 impl<'a, N> Ord for MatchResult<'a, N> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        self.score.total_cmp(&other.score)
     }
 }
 
+/// The headers of the fields serialized from a MatchResultSend
+const BASE_HEADERS: [&str; 5] = ["from_name", "from_id", "to_name", "to_id", "score"];
+
+/// The header of the column holding score explanations
+const EXPLANATION_HEADER: &str = "explanation";
+
+/// A MatchResult ranked by score, with equal scores ranked first in favour of a to
+/// name sharing the from name's separated legal form, and then by the position of
+/// the to name so that ties are broken deterministically in favour of earlier names.
+/// The position is that of the candidates searched, and of the name within them.
+#[derive(Debug)]
+pub struct RankedMatch<'a, N> {
+    pub result: MatchResult<'a, N>,
+    pub same_legal_form: bool,
+    pub to_index: (usize, usize),
+}
+
+impl<'a, N> PartialEq for RankedMatch<'a, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl<'a, N> Eq for RankedMatch<'a, N> {}
+impl<'a, N> PartialOrd for RankedMatch<'a, N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<'a, N> Ord for RankedMatch<'a, N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.result
+            .cmp(&other.result)
+            .then_with(|| self.same_legal_form.cmp(&other.same_legal_form))
+            .then_with(|| other.to_index.cmp(&self.to_index))
+    }
+}
+
+/// The results for the from name at `from_index`, in descending score order.
+#[derive(Debug)]
+pub struct MatchBatch {
+    pub from_index: usize,
+    pub results: Vec<MatchResultSend>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct MatchResultSend {
     pub from_name: String,
     pub from_id: String,
     pub to_name: String,
     pub to_id: String,
-    pub score: f64,
+    /// The match score, or None for a from name without a match
+    pub score: Option<f64>,
+    /// Values of the extra columns kept from the from file
+    #[serde(skip)]
+    pub from_kept: Vec<String>,
+    /// Values of the extra columns kept from the to file
+    #[serde(skip)]
+    pub to_kept: Vec<String>,
+    /// How the score was computed, when explanations are requested
+    #[serde(skip)]
+    pub explanation: Option<ScoreExplanation>,
+}
+
+impl MatchResultSend {
+    /// A result for a from name without a match, with empty to fields
+    pub fn unmatched<N: UnprocessedName>(from_name: &N) -> Self {
+        MatchResultSend {
+            from_name: from_name.unprocessed_name().to_string(),
+            from_id: from_name.idx().to_string(),
+            to_name: String::new(),
+            to_id: String::new(),
+            score: None,
+            from_kept: from_name.kept().to_vec(),
+            to_kept: vec![],
+            explanation: None,
+        }
+    }
+
+    /// The number of output fields in the result
+    pub fn width(&self) -> usize {
+        BASE_HEADERS.len()
+            + self.from_kept.len()
+            + self.to_kept.len()
+            + self.explanation.iter().count()
+    }
+
+    /// The output headers, with the kept columns of each file prefixed by `from_`
+    /// or `to_`.
+    pub fn headers(from_kept_columns: &[String], to_kept_columns: &[String]) -> Vec<String> {
+        BASE_HEADERS
+            .iter()
+            .map(|h| h.to_string())
+            .chain(from_kept_columns.iter().map(|c| format!("from_{}", c)))
+            .chain(to_kept_columns.iter().map(|c| format!("to_{}", c)))
+            .collect()
+    }
+
+    /// The output headers, followed by the explanation column when `explain` is set
+    pub fn headers_with_explanation(
+        from_kept_columns: &[String],
+        to_kept_columns: &[String],
+        explain: bool,
+    ) -> Vec<String> {
+        let mut headers = Self::headers(from_kept_columns, to_kept_columns);
+        if explain {
+            headers.push(EXPLANATION_HEADER.to_string());
+        }
+        headers
+    }
 }
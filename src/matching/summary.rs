@@ -0,0 +1,50 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::name::GROUP_KEY_SEPARATOR;
+
+/// A summary of a match run, reporting from-names that could not be matched
+/// within their group because no to-name shares it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchSummary {
+    /// The number of from-names
+    pub from_names: usize,
+    /// The number of from-names in each group without to-names
+    pub orphaned_groups: BTreeMap<String, usize>,
+    /// Whether orphaned from-names were matched against every to-name instead
+    pub group_fallback: bool,
+}
+
+impl MatchSummary {
+    /// The number of from-names in groups without to-names
+    pub fn orphaned_names(&self) -> usize {
+        self.orphaned_groups.values().sum()
+    }
+}
+
+impl fmt::Display for MatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Matched {} from names", self.from_names)?;
+        if self.orphaned_groups.is_empty() {
+            return Ok(());
+        }
+
+        let action = match self.group_fallback {
+            true => "searched all to names",
+            false => "skipped",
+        };
+        write!(
+            f,
+            "\n{} from names in {} groups without to names ({}):",
+            self.orphaned_names(),
+            self.orphaned_groups.len(),
+            action,
+        )?;
+        for (group, count) in &self.orphaned_groups {
+            // Show composite keys with a visible separator
+            let group = group.replace(GROUP_KEY_SEPARATOR, " | ");
+            write!(f, "\n  {} ({})", group, count)?;
+        }
+        Ok(())
+    }
+}
@@ -1,21 +1,74 @@
 use anyhow;
 use counter::Counter;
 use getset::Getters;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::core::idf::TokenDocument;
-use crate::core::vec_from_csv;
+use crate::core::{read_table, InputOptions, Table};
 
 pub trait UnprocessedName {
     fn unprocessed_name(&self) -> &str;
     fn idx(&self) -> &str;
-    fn from_csv(file_path: &str) -> anyhow::Result<Vec<Self>>
+    /// The values of the extra columns kept from the source file
+    fn kept(&self) -> &[String];
+    /// The group of a grouped name
+    fn group_key(&self) -> Option<&str>;
+    /// Read the names from a tabular data file in any input format
+    fn from_file(
+        file_path: &str,
+        columns: &NameColumns,
+        input_options: &InputOptions,
+    ) -> anyhow::Result<NameFile<Self>>
     where
         Self: Sized;
+
+    /// Read the names from a comma separated file
+    fn from_csv(file_path: &str, columns: &NameColumns) -> anyhow::Result<NameFile<Self>>
+    where
+        Self: Sized,
+    {
+        Self::from_file(file_path, columns, &InputOptions::default())
+    }
+}
+
+/// The columns of a tabular data file holding the name, its identifier and its
+/// group. The identifier column is optional in the file. Several group columns
+/// form a composite group key. Columns listed in `keep`,
+/// or all other columns with `keep_all`, are carried along with each name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NameColumns {
+    pub name: String,
+    pub id: String,
+    pub group: Vec<String>,
+    pub keep: Vec<String>,
+    pub keep_all: bool,
+}
+
+impl Default for NameColumns {
+    fn default() -> Self {
+        NameColumns {
+            name: "name".to_string(),
+            id: "id".to_string(),
+            group: vec!["group".to_string()],
+            keep: vec![],
+            keep_all: false,
+        }
+    }
+}
+
+/// The names read from a tabular data file, along with the headers of the extra
+/// columns kept with each name.
+#[derive(Debug)]
+pub struct NameFile<N> {
+    pub names: Vec<N>,
+    pub kept_columns: Vec<String>,
 }
 
 pub trait NameContainer<N> {
     fn get_name(&self) -> &N;
+    /// The legal form separated from the name, if any
+    fn legal_form(&self) -> Option<&str>;
 }
 
 /// An unprocessed Name capable of serialization from/to a tabular data file.
@@ -27,6 +80,18 @@ pub struct NameUngrouped {
     #[getset(get = "pub")]
     #[serde(rename = "id", default)]
     idx: String,
+    #[serde(default)]
+    kept: Vec<String>,
+}
+
+impl NameUngrouped {
+    pub fn new(unprocessed: String, idx: String) -> Self {
+        NameUngrouped {
+            unprocessed,
+            idx,
+            kept: vec![],
+        }
+    }
 }
 
 impl UnprocessedName for NameUngrouped {
@@ -38,13 +103,47 @@ impl UnprocessedName for NameUngrouped {
         &self.idx
     }
 
-    fn from_csv(file_path: &str) -> anyhow::Result<Vec<NameUngrouped>> {
-        vec_from_csv(file_path)
+    fn kept(&self) -> &[String] {
+        &self.kept
+    }
+
+    fn group_key(&self) -> Option<&str> {
+        None
+    }
+
+    fn from_file(
+        file_path: &str,
+        columns: &NameColumns,
+        input_options: &InputOptions,
+    ) -> anyhow::Result<NameFile<Self>> {
+        let table = read_table(file_path, input_options)?;
+        let positions = ColumnPositions::new(&table, columns, false, file_path)?;
+
+        let names = table
+            .records
+            .map(|record| {
+                let record = record?;
+                Ok(NameUngrouped {
+                    unprocessed: field(&record, Some(positions.name)),
+                    idx: field(&record, positions.id),
+                    kept: positions.kept_values(&record),
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(NameFile {
+            names,
+            kept_columns: positions.kept_columns,
+        })
     }
 }
 
+/// Separates the values of each column in a composite group key
+pub const GROUP_KEY_SEPARATOR: char = '\u{1f}';
+
 /// An unprocessed Name capable of serialization from/to a tabular data file. Includes group
-/// identifier string.
+/// identifier string, which joins the values of composite group columns with
+/// `GROUP_KEY_SEPARATOR`.
 #[derive(Debug, Serialize, Deserialize, Getters)]
 pub struct NameGrouped {
     #[getset(get = "pub")]
@@ -55,6 +154,19 @@ pub struct NameGrouped {
     idx: String,
     #[getset(get = "pub")]
     group: String,
+    #[serde(default)]
+    kept: Vec<String>,
+}
+
+impl NameGrouped {
+    pub fn new(unprocessed: String, idx: String, group: String) -> Self {
+        NameGrouped {
+            unprocessed,
+            idx,
+            group,
+            kept: vec![],
+        }
+    }
 }
 
 impl UnprocessedName for NameGrouped {
@@ -66,19 +178,127 @@ impl UnprocessedName for NameGrouped {
         &self.idx
     }
 
-    fn from_csv(file_path: &str) -> anyhow::Result<Vec<NameGrouped>> {
-        vec_from_csv(file_path)
+    fn kept(&self) -> &[String] {
+        &self.kept
+    }
+
+    fn group_key(&self) -> Option<&str> {
+        Some(&self.group)
+    }
+
+    fn from_file(
+        file_path: &str,
+        columns: &NameColumns,
+        input_options: &InputOptions,
+    ) -> anyhow::Result<NameFile<Self>> {
+        let table = read_table(file_path, input_options)?;
+        let positions = ColumnPositions::new(&table, columns, true, file_path)?;
+
+        let names = table
+            .records
+            .map(|record| {
+                let record = record?;
+                Ok(NameGrouped {
+                    unprocessed: field(&record, Some(positions.name)),
+                    idx: field(&record, positions.id),
+                    group: positions
+                        .group
+                        .iter()
+                        .map(|&p| field(&record, Some(p)))
+                        .join(&GROUP_KEY_SEPARATOR.to_string()),
+                    kept: positions.kept_values(&record),
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(NameFile {
+            names,
+            kept_columns: positions.kept_columns,
+        })
+    }
+}
+
+/// The positions of the name columns in a table
+struct ColumnPositions {
+    name: usize,
+    id: Option<usize>,
+    group: Vec<usize>,
+    kept: Vec<usize>,
+    kept_columns: Vec<String>,
+}
+
+impl ColumnPositions {
+    /// Find the columns in the table headers. The group columns are only required
+    /// for grouped names. Keeping all columns keeps every column except the name
+    /// and id, which are always output.
+    fn new(
+        table: &Table,
+        columns: &NameColumns,
+        grouped: bool,
+        file_path: &str,
+    ) -> anyhow::Result<Self> {
+        let name = table.required_column_position(&columns.name, file_path)?;
+        let id = table.column_position(&columns.id);
+        let group = match grouped {
+            true => columns
+                .group
+                .iter()
+                .map(|c| table.required_column_position(c, file_path))
+                .collect::<anyhow::Result<_>>()?,
+            false => vec![],
+        };
+
+        let (kept_columns, kept) = if columns.keep_all {
+            table
+                .headers
+                .iter()
+                .enumerate()
+                .filter(|(_, h)| **h != columns.name && **h != columns.id)
+                .map(|(p, h)| (h.to_string(), p))
+                .unzip()
+        } else {
+            let positions = columns
+                .keep
+                .iter()
+                .map(|c| table.required_column_position(c, file_path))
+                .collect::<anyhow::Result<_>>()?;
+            (columns.keep.clone(), positions)
+        };
+
+        Ok(ColumnPositions {
+            name,
+            id,
+            group,
+            kept,
+            kept_columns,
+        })
+    }
+
+    fn kept_values(&self, record: &[String]) -> Vec<String> {
+        self.kept.iter().map(|&p| field(record, Some(p))).collect()
     }
 }
 
+/// The field at `position` of a record, or an empty string if the column is absent.
+fn field(record: &[String], position: Option<usize>) -> String {
+    position
+        .and_then(|p| record.get(p))
+        .cloned()
+        .unwrap_or_default()
+}
+
 /// A processed Name with a counter for each token, use the new constructor
 /// with a passed in text processing function.
-#[derive(Debug, Getters)]
+#[derive(Debug, Getters, Serialize, Deserialize)]
 pub struct NameProcessed<N> {
     #[getset(get = "pub")]
     pub name: N,
     #[getset(get = "pub")]
+    #[serde(with = "token_counts")]
     pub token_counter: Counter<String>,
+    /// The legal form separated from a company name, see `LegalForm::Separate`
+    #[getset(get = "pub")]
+    pub legal_form: Option<String>,
 }
 
 impl<N> NameProcessed<N> {
@@ -90,8 +310,31 @@ impl<N> NameProcessed<N> {
         NameProcessed {
             name,
             token_counter,
+            legal_form: None,
         }
     }
+
+    pub fn with_legal_form(mut self, legal_form: Option<String>) -> Self {
+        self.legal_form = legal_form;
+        self
+    }
+}
+
+/// (De)serialize a token counter as a map from each token to its count
+pub mod token_counts {
+    use std::collections::HashMap;
+
+    use counter::Counter;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(counter: &Counter<String>, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_map(counter.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Counter<String>, D::Error> {
+        let counts: HashMap<String, usize> = HashMap::deserialize(d)?;
+        Ok(counts.into_iter().collect())
+    }
 }
 
 impl<N> TokenDocument for NameProcessed<N> {
@@ -100,3 +343,64 @@ impl<N> TokenDocument for NameProcessed<N> {
         self.token_counter().keys().collect()
     }
 }
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::TempFile;
+
+    #[test]
+    fn from_csv_with_named_columns() {
+        let csv = TempFile::new(
+            "columns",
+            "gvkey,conm,sic\n001,Psych Inc,7381\n002,Blueberry Co,2033\n",
+        );
+        let path = csv.path();
+
+        let columns = NameColumns {
+            name: "conm".to_string(),
+            id: "gvkey".to_string(),
+            group: vec!["sic".to_string()],
+            ..NameColumns::default()
+        };
+        let names = NameGrouped::from_csv(path, &columns).unwrap().names;
+        assert_eq!(names[1].unprocessed(), "Blueberry Co");
+        assert_eq!(names[1].idx(), "002");
+        assert_eq!(names[1].group(), "2033");
+
+        // Several group columns form a composite key
+        let composite = NameColumns {
+            group: vec!["sic".to_string(), "gvkey".to_string()],
+            ..columns.clone()
+        };
+        let names = NameGrouped::from_csv(path, &composite).unwrap().names;
+        assert_eq!(names[1].group(), "2033\u{1f}002");
+
+        // The id column is optional, the name column is not
+        let columns = NameColumns {
+            id: "permno".to_string(),
+            ..columns
+        };
+        let names = NameUngrouped::from_csv(path, &columns).unwrap().names;
+        assert_eq!(names[0].idx(), "");
+        assert!(NameUngrouped::from_csv(path, &NameColumns::default()).is_err());
+
+        // Kept columns
+        let keep_all = NameColumns {
+            keep_all: true,
+            ..columns.clone()
+        };
+        let file = NameUngrouped::from_csv(path, &keep_all).unwrap();
+        assert_eq!(file.kept_columns, vec!["gvkey", "sic"]);
+        assert_eq!(file.names[0].kept(), ["001", "7381"]);
+
+        let keep_missing = NameColumns {
+            keep: vec!["state".to_string()],
+            ..columns
+        };
+        assert!(NameUngrouped::from_csv(path, &keep_missing).is_err());
+    }
+}
@@ -1,21 +1,26 @@
 use counter::Counter;
 use getset::Getters;
 use itertools::iproduct;
-use strsim::{normalized_damerau_levenshtein, normalized_levenshtein};
+use serde::{Deserialize, Serialize};
+use strsim::{jaro, normalized_damerau_levenshtein, normalized_levenshtein};
 
 use crate::core::idf::Idf;
-use crate::name::base::{NameContainer, NameProcessed};
-use crate::name::score_combination_queue;
+use crate::name::base::{token_counts, NameContainer, NameProcessed};
+use crate::name::score::{
+    explain_combination_queue, score_combination_queue, weigh_tokens, ScoreExplanation, TokenPair,
+};
 
 /*****************************************************************************/
 /* Levenshtein name for approximate  matching                                */
 /*****************************************************************************/
 /// A Name using Levenshtein distance suitable for matching
-#[derive(Debug, Getters)]
+#[derive(Debug, Getters, Serialize, Deserialize)]
 pub struct NameLevenshtein<N> {
     #[getset(get = "pub")]
     name: N,
+    legal_form: Option<String>,
     #[getset(get = "pub")]
+    #[serde(with = "token_counts")]
     token_counter: Counter<String>,
     #[getset(get = "pub")]
     token_weights: Vec<(String, f64)>,
@@ -27,60 +32,71 @@ impl<N> NameContainer<N> for NameLevenshtein<N> {
     fn get_name(&self) -> &N {
         &self.name
     }
+
+    fn legal_form(&self) -> Option<&str> {
+        self.legal_form.as_deref()
+    }
 }
 
 impl<N> NameLevenshtein<N> {
     pub fn new(np: NameProcessed<N>, idf: &Idf) -> Self {
         let token_counter: Counter<String> = np.token_counter;
-        let mut token_weights = vec![];
-        let mut total_weight: f64 = 0.0;
-
-        for (token, count) in token_counter.iter() {
-            let weight = idf.lookup(token);
-            token_weights.push((token.to_string(), weight));
-
-            total_weight += (*count as f64) * weight.powi(2);
-        }
+        let (token_weights, norm) = weigh_tokens(&token_counter, idf);
 
         NameLevenshtein {
             name: np.name,
+            legal_form: np.legal_form,
             token_counter,
             token_weights,
-            norm: total_weight.sqrt(),
+            norm,
         }
     }
 
     pub fn compute_match_score(&self, to_name: &Self) -> f64 {
-        let mut combination_queue: Vec<_> = vec![];
-
-        for ((from_token, from_weight), (to_token, to_weight)) in
-            iproduct!(&self.token_weights, &to_name.token_weights)
-        {
-            combination_queue.push((
-                normalized_levenshtein(from_token, to_token) * from_weight * to_weight,
-                from_token,
-                to_token,
-            ))
-        }
-
         score_combination_queue(
             &self.token_counter,
             self.norm,
             &to_name.token_counter,
             to_name.norm,
-            combination_queue,
+            self.combination_queue(to_name),
+        )
+    }
+
+    pub fn explain_match_score(&self, to_name: &Self) -> ScoreExplanation {
+        explain_combination_queue(
+            &self.token_counter,
+            self.norm,
+            &to_name.token_counter,
+            to_name.norm,
+            self.combination_queue(to_name),
         )
     }
+
+    fn combination_queue<'a>(&'a self, to_name: &'a Self) -> Vec<TokenPair<'a>> {
+        iproduct!(&self.token_weights, &to_name.token_weights)
+            .map(
+                |((from_token, from_weight), (to_token, to_weight))| TokenPair {
+                    from_token,
+                    to_token,
+                    similarity: normalized_levenshtein(from_token, to_token),
+                    from_weight: *from_weight,
+                    to_weight: *to_weight,
+                },
+            )
+            .collect()
+    }
 }
 
 /*****************************************************************************/
 /* Damerau-Levenshtein name for approximate  matching                        */
 /*****************************************************************************/
-#[derive(Debug, Getters)]
+#[derive(Debug, Getters, Serialize, Deserialize)]
 pub struct NameDamerauLevenshtein<N> {
     #[getset(get = "pub")]
     name: N,
+    legal_form: Option<String>,
     #[getset(get = "pub")]
+    #[serde(with = "token_counts")]
     token_counter: Counter<String>,
     #[getset(get = "pub")]
     token_weights: Vec<(String, f64)>,
@@ -92,48 +108,190 @@ impl<N> NameContainer<N> for NameDamerauLevenshtein<N> {
     fn get_name(&self) -> &N {
         &self.name
     }
+
+    fn legal_form(&self) -> Option<&str> {
+        self.legal_form.as_deref()
+    }
 }
 
 impl<N> NameDamerauLevenshtein<N> {
     pub fn new(np: NameProcessed<N>, idf: &Idf) -> Self {
         let token_counter: Counter<String> = np.token_counter;
-        let mut token_weights = vec![];
-        let mut total_weight: f64 = 0.0;
-
-        for (token, count) in token_counter.iter() {
-            let weight = idf.lookup(token);
-            token_weights.push((token.to_string(), weight));
-
-            total_weight += (*count as f64) * weight.powi(2);
-        }
+        let (token_weights, norm) = weigh_tokens(&token_counter, idf);
 
         NameDamerauLevenshtein {
             name: np.name,
+            legal_form: np.legal_form,
             token_counter,
             token_weights,
-            norm: total_weight.sqrt(),
+            norm,
         }
     }
 
     pub fn compute_match_score(&self, to_name: &Self) -> f64 {
-        let mut combination_queue: Vec<_> = vec![];
-
-        for ((from_token, from_weight), (to_token, to_weight)) in
-            iproduct!(&self.token_weights, &to_name.token_weights)
-        {
-            combination_queue.push((
-                normalized_damerau_levenshtein(from_token, to_token) * from_weight * to_weight,
-                from_token,
-                to_token,
-            ))
+        score_combination_queue(
+            &self.token_counter,
+            self.norm,
+            &to_name.token_counter,
+            to_name.norm,
+            self.combination_queue(to_name),
+        )
+    }
+
+    pub fn explain_match_score(&self, to_name: &Self) -> ScoreExplanation {
+        explain_combination_queue(
+            &self.token_counter,
+            self.norm,
+            &to_name.token_counter,
+            to_name.norm,
+            self.combination_queue(to_name),
+        )
+    }
+
+    fn combination_queue<'a>(&'a self, to_name: &'a Self) -> Vec<TokenPair<'a>> {
+        iproduct!(&self.token_weights, &to_name.token_weights)
+            .map(
+                |((from_token, from_weight), (to_token, to_weight))| TokenPair {
+                    from_token,
+                    to_token,
+                    similarity: normalized_damerau_levenshtein(from_token, to_token),
+                    from_weight: *from_weight,
+                    to_weight: *to_weight,
+                },
+            )
+            .collect()
+    }
+}
+
+/*****************************************************************************/
+/* Jaro-Winkler name for approximate  matching                               */
+/*****************************************************************************/
+/// A Name using Jaro-Winkler similarity suitable for matching
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub struct NameJaroWinkler<N> {
+    #[getset(get = "pub")]
+    name: N,
+    legal_form: Option<String>,
+    #[getset(get = "pub")]
+    #[serde(with = "token_counts")]
+    token_counter: Counter<String>,
+    #[getset(get = "pub")]
+    token_weights: Vec<(String, f64)>,
+    #[getset(get = "pub")]
+    norm: f64,
+}
+
+impl<N> NameContainer<N> for NameJaroWinkler<N> {
+    fn get_name(&self) -> &N {
+        &self.name
+    }
+
+    fn legal_form(&self) -> Option<&str> {
+        self.legal_form.as_deref()
+    }
+}
+
+impl<N> NameJaroWinkler<N> {
+    pub fn new(np: NameProcessed<N>, idf: &Idf) -> Self {
+        let token_counter: Counter<String> = np.token_counter;
+        let (token_weights, norm) = weigh_tokens(&token_counter, idf);
+
+        NameJaroWinkler {
+            name: np.name,
+            legal_form: np.legal_form,
+            token_counter,
+            token_weights,
+            norm,
         }
+    }
 
+    pub fn compute_match_score(&self, to_name: &Self, prefix_scale: f64) -> f64 {
         score_combination_queue(
             &self.token_counter,
             self.norm,
             &to_name.token_counter,
             to_name.norm,
-            combination_queue,
+            self.combination_queue(to_name, prefix_scale),
         )
     }
+
+    pub fn explain_match_score(&self, to_name: &Self, prefix_scale: f64) -> ScoreExplanation {
+        explain_combination_queue(
+            &self.token_counter,
+            self.norm,
+            &to_name.token_counter,
+            to_name.norm,
+            self.combination_queue(to_name, prefix_scale),
+        )
+    }
+
+    fn combination_queue<'a>(&'a self, to_name: &'a Self, prefix_scale: f64) -> Vec<TokenPair<'a>> {
+        iproduct!(&self.token_weights, &to_name.token_weights)
+            .map(
+                |((from_token, from_weight), (to_token, to_weight))| TokenPair {
+                    from_token,
+                    to_token,
+                    similarity: jaro_winkler(from_token, to_token, prefix_scale),
+                    from_weight: *from_weight,
+                    to_weight: *to_weight,
+                },
+            )
+            .collect()
+    }
+}
+
+/// The maximum common prefix length rewarded by Jaro-Winkler similarity
+const JARO_WINKLER_MAX_PREFIX: usize = 4;
+
+/// The Jaro similarity above which Jaro-Winkler similarity rewards a common prefix
+const JARO_WINKLER_BOOST_THRESHOLD: f64 = 0.7;
+
+/// Jaro-Winkler similarity, boosting a Jaro similarity above 0.7 by `prefix_scale`
+/// for each character of common prefix up to four characters. A `prefix_scale`
+/// no larger than 0.25 keeps the similarity within [0, 1].
+pub fn jaro_winkler(a: &str, b: &str, prefix_scale: f64) -> f64 {
+    let jaro_similarity = jaro(a, b);
+    if jaro_similarity <= JARO_WINKLER_BOOST_THRESHOLD {
+        return jaro_similarity;
+    }
+    let prefix_length = a
+        .chars()
+        .zip(b.chars())
+        .take_while(|(a_char, b_char)| a_char == b_char)
+        .take(JARO_WINKLER_MAX_PREFIX)
+        .count();
+
+    jaro_similarity + prefix_length as f64 * prefix_scale * (1.0 - jaro_similarity)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn jaro_winkler_prefix_scale() {
+        assert_approx_eq!(jaro_winkler("martha", "marhta", 0.1), 0.961111, 1e-6);
+        assert_approx_eq!(jaro_winkler("dwayne", "duane", 0.1), 0.84, 1e-6);
+        assert_approx_eq!(
+            jaro_winkler("dwayne", "duane", 0.0),
+            jaro("dwayne", "duane")
+        );
+
+        // Only the first four characters of a common prefix are rewarded
+        let j = jaro("abcdefgh", "abcdefgx");
+        assert_approx_eq!(
+            jaro_winkler("abcdefgh", "abcdefgx", 0.1),
+            j + 0.4 * (1.0 - j)
+        );
+        assert_approx_eq!(jaro_winkler("smith", "smith", 0.25), 1.0);
+
+        // Dissimilar tokens sharing a prefix are not boosted
+        let j = jaro("abcxyz", "abdpqr");
+        assert!(j <= 0.7);
+        assert_eq!(jaro_winkler("abcxyz", "abdpqr", 0.1), j);
+    }
 }
@@ -1,12 +1,13 @@
 pub mod base;
 pub mod distance;
 pub mod ngram;
+pub mod person;
 pub mod score;
 pub mod token;
 
 pub use crate::name::base::*;
 pub use crate::name::distance::*;
 pub use crate::name::ngram::*;
+pub use crate::name::person::*;
+pub use crate::name::score::{PairExplanation, ScoreExplanation};
 pub use crate::name::token::*;
-
-use crate::name::score::*;
@@ -4,20 +4,25 @@ use counter::Counter;
 use getset::Getters;
 use itertools::iproduct;
 use ngrams::Ngram;
+use serde::{Deserialize, Serialize};
 
 use crate::core::Idf;
-use crate::name::score::score_combination_queue;
-use crate::name::{NameContainer, NameProcessed};
+use crate::name::score::{
+    explain_combination_queue, score_combination_queue, ScoreExplanation, TokenPair,
+};
+use crate::name::{token_counts, NameContainer, NameProcessed};
 
 /*****************************************************************************/
 /* Ngram name for approximate  matching                                      */
 /*****************************************************************************/
 /// A Name using NGrams suitable for matching
-#[derive(Debug, Getters)]
+#[derive(Debug, Getters, Serialize, Deserialize)]
 pub struct NameNGrams<N> {
     #[getset(get = "pub")]
     name: N,
+    legal_form: Option<String>,
     #[getset(get = "pub")]
+    #[serde(with = "token_counts")]
     token_counter: Counter<String>,
     #[getset(get = "pub")]
     token_ngram_weights: Vec<(String, NGram, f64)>,
@@ -29,6 +34,10 @@ impl<N> NameContainer<N> for NameNGrams<N> {
     fn get_name(&self) -> &N {
         &self.name
     }
+
+    fn legal_form(&self) -> Option<&str> {
+        self.legal_form.as_deref()
+    }
 }
 
 impl<N> NameNGrams<N> {
@@ -50,13 +59,42 @@ impl<N> NameNGrams<N> {
 
         NameNGrams {
             name: np.name,
+            legal_form: np.legal_form,
             token_counter,
             token_ngram_weights,
             norm: total_weight.sqrt(),
         }
     }
 
+    /// The n-grams of every token in the name
+    pub fn ngrams(&self) -> Vec<&String> {
+        self.token_ngram_weights
+            .iter()
+            .flat_map(|(_, ngram, _)| ngram.ngrams())
+            .collect()
+    }
+
     pub fn compute_match_score(&self, to_name: &Self) -> f64 {
+        score_combination_queue(
+            &self.token_counter,
+            self.norm,
+            &to_name.token_counter,
+            to_name.norm,
+            self.combination_queue(to_name),
+        )
+    }
+
+    pub fn explain_match_score(&self, to_name: &Self) -> ScoreExplanation {
+        explain_combination_queue(
+            &self.token_counter,
+            self.norm,
+            &to_name.token_counter,
+            to_name.norm,
+            self.combination_queue(to_name),
+        )
+    }
+
+    fn combination_queue<'a>(&'a self, to_name: &'a Self) -> Vec<TokenPair<'a>> {
         let mut combination_queue: Vec<_> = vec![];
 
         for ((from_token, from_ngram, from_weight), (to_token, to_ngram, to_weight)) in
@@ -72,34 +110,37 @@ impl<N> NameNGrams<N> {
                     min(count_in_from, count_in_to)
                 })
                 .sum();
-            combination_queue.push((
-                ngrams_in_common as f64 / (len_ngram_self as f64 * len_ngram_to as f64).sqrt()
-                    * from_weight
-                    * to_weight,
+            combination_queue.push(TokenPair {
                 from_token,
                 to_token,
-            ))
+                similarity: ngrams_in_common as f64
+                    / (len_ngram_self as f64 * len_ngram_to as f64).sqrt(),
+                from_weight: *from_weight,
+                to_weight: *to_weight,
+            })
         }
 
-        score_combination_queue(
-            &self.token_counter,
-            self.norm,
-            &to_name.token_counter,
-            to_name.norm,
-            combination_queue,
-        )
+        combination_queue
     }
 }
 
 /*****************************************************************************/
 /* NGram related                                                             */
 /*****************************************************************************/
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct NGram {
+    #[serde(with = "token_counts")]
     ngram_counter: Counter<String>,
     n_ngrams: usize,
 }
 
+impl NGram {
+    /// The distinct n-grams in the token
+    pub fn ngrams(&self) -> impl Iterator<Item = &String> {
+        self.ngram_counter.keys()
+    }
+}
+
 fn n_gram(s: String, window_size: usize) -> NGram {
     if window_size <= 1 {
         panic!("preprocess::PrepString.n_gram requires a window_size of 2 or greater")
@@ -128,6 +169,7 @@ fn mconcat_chars(cs: Vec<char>) -> String {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::name::NameUngrouped;
     use assert_approx_eq::assert_approx_eq;
 
     #[test]
@@ -190,14 +232,8 @@ mod test {
         let john_smith = "john smith";
         let jon_smyth = "jonnn sssmyth";
 
-        let name_0 = Name {
-            unprocessed: john_smith.to_string(),
-            idx: "1".to_string(),
-        };
-        let name_1 = Name {
-            unprocessed: jon_smyth.to_string(),
-            idx: "1".to_string(),
-        };
+        let name_0 = NameUngrouped::new(john_smith.to_string(), "1".to_string());
+        let name_1 = NameUngrouped::new(jon_smyth.to_string(), "1".to_string());
 
         let np_0 = NameProcessed::new(
             name_0,
@@ -224,8 +260,8 @@ mod test {
         let ms_flipped = ng_1.compute_match_score(&ng_0);
         let ms_self_0 = ng_0.compute_match_score(&ng_0);
         let ms_self_1 = ng_1.compute_match_score(&ng_1);
-        assert_approx_eq!(ms, (0.562536 as f64));
-        assert_approx_eq!(ms_flipped, (0.562536 as f64));
+        assert_approx_eq!(ms, 0.562536);
+        assert_approx_eq!(ms_flipped, 0.562536);
         assert_approx_eq!(ms_self_0, 1.0);
         assert_approx_eq!(ms_self_1, 1.0);
     }
@@ -0,0 +1,185 @@
+use counter::Counter;
+use getset::Getters;
+use itertools::iproduct;
+use serde::{Deserialize, Serialize};
+
+use crate::core::Idf;
+use crate::name::score::{
+    explain_combination_queue, score_combination_queue, weigh_tokens, ScoreExplanation, TokenPair,
+};
+use crate::name::{token_counts, NameContainer, NameProcessed};
+
+/*****************************************************************************/
+/* Person name for matching with initials                                    */
+/*****************************************************************************/
+/// A person's Name suitable for matching, where an initial matches any token
+/// starting with its letter
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub struct NamePerson<N> {
+    #[getset(get = "pub")]
+    name: N,
+    legal_form: Option<String>,
+    #[getset(get = "pub")]
+    #[serde(with = "token_counts")]
+    token_counter: Counter<String>,
+    #[getset(get = "pub")]
+    token_weights: Vec<(String, f64)>,
+    #[getset(get = "pub")]
+    norm: f64,
+    /// The keys the name is indexed by as a to name: its tokens, and the initial
+    /// of each longer token marked with a trailing `*`
+    #[getset(get = "pub")]
+    index_keys: Vec<String>,
+}
+
+impl<N> NameContainer<N> for NamePerson<N> {
+    fn get_name(&self) -> &N {
+        &self.name
+    }
+
+    fn legal_form(&self) -> Option<&str> {
+        self.legal_form.as_deref()
+    }
+}
+
+impl<N> NamePerson<N> {
+    pub fn new(np: NameProcessed<N>, idf: &Idf) -> Self {
+        let token_counter: Counter<String> = np.token_counter;
+        let (token_weights, norm) = weigh_tokens(&token_counter, idf);
+
+        let index_keys = token_counter
+            .keys()
+            .flat_map(|token| match initial(token) {
+                Some(_) => vec![token.to_string()],
+                None => vec![token.to_string(), format!("{}*", first_char(token))],
+            })
+            .collect();
+
+        NamePerson {
+            name: np.name,
+            legal_form: np.legal_form,
+            token_counter,
+            token_weights,
+            norm,
+            index_keys,
+        }
+    }
+
+    /// The keys under which the to names that may match this from name are
+    /// indexed: its tokens, the initials of longer tokens, and the marked
+    /// initials of the initials
+    pub fn lookup_keys(&self) -> Vec<String> {
+        self.token_counter
+            .keys()
+            .flat_map(|token| match initial(token) {
+                Some(_) => vec![token.to_string(), format!("{}*", token)],
+                None => vec![token.to_string(), first_char(token)],
+            })
+            .collect()
+    }
+
+    pub fn compute_match_score(&self, to_name: &Self, initial_similarity: f64) -> f64 {
+        score_combination_queue(
+            &self.token_counter,
+            self.norm,
+            &to_name.token_counter,
+            to_name.norm,
+            self.combination_queue(to_name, initial_similarity),
+        )
+    }
+
+    pub fn explain_match_score(&self, to_name: &Self, initial_similarity: f64) -> ScoreExplanation {
+        explain_combination_queue(
+            &self.token_counter,
+            self.norm,
+            &to_name.token_counter,
+            to_name.norm,
+            self.combination_queue(to_name, initial_similarity),
+        )
+    }
+
+    fn combination_queue<'a>(
+        &'a self,
+        to_name: &'a Self,
+        initial_similarity: f64,
+    ) -> Vec<TokenPair<'a>> {
+        iproduct!(&self.token_weights, &to_name.token_weights)
+            .map(
+                |((from_token, from_weight), (to_token, to_weight))| TokenPair {
+                    from_token,
+                    to_token,
+                    similarity: person_similarity(from_token, to_token, initial_similarity),
+                    from_weight: *from_weight,
+                    to_weight: *to_weight,
+                },
+            )
+            .filter(|pair| pair.similarity > 0.0)
+            .collect()
+    }
+}
+
+/// The similarity of two name parts: one if they are equal, `initial_similarity`
+/// if one is the initial of the other, and zero otherwise.
+pub fn person_similarity(a: &str, b: &str, initial_similarity: f64) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    match (initial(a), initial(b)) {
+        (Some(i), None) if b.starts_with(i) => initial_similarity,
+        (None, Some(i)) if a.starts_with(i) => initial_similarity,
+        _ => 0.0,
+    }
+}
+
+/// The token itself when it is a single character
+fn initial(token: &str) -> Option<char> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+fn first_char(token: &str) -> String {
+    token.chars().take(1).collect()
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::IdfScheme;
+    use crate::name::NameUngrouped;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn initials_match_name_parts() {
+        assert_eq!(person_similarity("john", "john", 0.8), 1.0);
+        assert_eq!(person_similarity("j", "john", 0.8), 0.8);
+        assert_eq!(person_similarity("john", "j", 0.8), 0.8);
+        assert_eq!(person_similarity("j", "k", 0.8), 0.0);
+        assert_eq!(person_similarity("jo", "john", 0.8), 0.0);
+
+        let person = |name: &str, tokens: &[&str]| {
+            let np = NameProcessed::new(
+                NameUngrouped::new(name.to_string(), "1".to_string()),
+                tokens.iter().map(|t| t.to_string()),
+            );
+            NamePerson::new(np, &Idf::from_documents(vec![], IdfScheme::Uniform))
+        };
+        let from = person("J. Smith", &["j", "smith"]);
+        let to = person("John Smith", &["john", "smith"]);
+
+        // The initial of the from name finds the to name, and the reverse
+        assert!(from.lookup_keys().contains(&"j*".to_string()));
+        assert!(to.index_keys().contains(&"j*".to_string()));
+        assert!(to.lookup_keys().contains(&"j".to_string()));
+
+        let explanation = from.explain_match_score(&to, 0.8);
+        assert_eq!(explanation.pairs.len(), 2);
+        assert_approx_eq!(from.compute_match_score(&to, 0.8), 0.9);
+        assert_approx_eq!(from.compute_match_score(&to, 1.0), 1.0);
+    }
+}
@@ -1,36 +1,140 @@
 use counter::Counter;
+use serde::Serialize;
+
+use crate::core::Idf;
+
+/// A from token paired with a to token, along with their similarity and IDF weights
+#[derive(Debug, Clone, Copy)]
+pub struct TokenPair<'a> {
+    pub from_token: &'a String,
+    pub to_token: &'a String,
+    pub similarity: f64,
+    pub from_weight: f64,
+    pub to_weight: f64,
+}
+
+impl<'a> TokenPair<'a> {
+    /// The contribution of the pair to the unnormalized score
+    pub fn weighted_similarity(&self) -> f64 {
+        self.similarity * self.from_weight * self.to_weight
+    }
+}
+
+/// A token pairing chosen when scoring a match
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PairExplanation {
+    pub from_token: String,
+    pub to_token: String,
+    pub similarity: f64,
+    pub from_weight: f64,
+    pub to_weight: f64,
+}
+
+impl<'a> From<&TokenPair<'a>> for PairExplanation {
+    fn from(pair: &TokenPair<'a>) -> Self {
+        PairExplanation {
+            from_token: pair.from_token.to_string(),
+            to_token: pair.to_token.to_string(),
+            similarity: pair.similarity,
+            from_weight: pair.from_weight,
+            to_weight: pair.to_weight,
+        }
+    }
+}
+
+/// How a match score was computed: the sum of the weighted similarities of the
+/// chosen token pairs, divided by the product of the two name norms. A pair is
+/// listed once for each time it was chosen.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScoreExplanation {
+    pub pairs: Vec<PairExplanation>,
+    pub from_norm: f64,
+    pub to_norm: f64,
+    pub score: f64,
+}
+
+/// The IDF weight of each distinct token, along with the norm of the name: the
+/// square root of the summed squared weights of every token occurrence
+pub fn weigh_tokens(token_counter: &Counter<String>, idf: &Idf) -> (Vec<(String, f64)>, f64) {
+    let mut token_weights = vec![];
+    let mut total_weight: f64 = 0.0;
+
+    for (token, count) in token_counter.iter() {
+        let weight = idf.lookup(token);
+        token_weights.push((token.to_string(), weight));
+
+        total_weight += (*count as f64) * weight.powi(2);
+    }
+    (token_weights, total_weight.sqrt())
+}
 
 pub fn score_combination_queue(
     from_token_counter: &Counter<String>,
     from_norm: f64,
     to_token_counter: &Counter<String>,
     to_norm: f64,
-    combination_queue: Vec<(f64, &String, &String)>,
+    combination_queue: Vec<TokenPair>,
 ) -> f64 {
+    let score_in_common: f64 =
+        choose_pairs(from_token_counter, to_token_counter, combination_queue)
+            .iter()
+            .map(|pair| pair.weighted_similarity())
+            .sum();
+
+    score_in_common / (from_norm * to_norm)
+}
+
+/// Explain the score given by `score_combination_queue`
+pub fn explain_combination_queue(
+    from_token_counter: &Counter<String>,
+    from_norm: f64,
+    to_token_counter: &Counter<String>,
+    to_norm: f64,
+    combination_queue: Vec<TokenPair>,
+) -> ScoreExplanation {
+    let pairs = choose_pairs(from_token_counter, to_token_counter, combination_queue);
+    let score_in_common: f64 = pairs.iter().map(|pair| pair.weighted_similarity()).sum();
+
+    ScoreExplanation {
+        pairs: pairs.iter().map(PairExplanation::from).collect(),
+        from_norm,
+        to_norm,
+        score: score_in_common / (from_norm * to_norm),
+    }
+}
+
+/// Greedily pair tokens, best first, until every occurrence of a token on either
+/// side has been used.
+fn choose_pairs<'a>(
+    from_token_counter: &Counter<String>,
+    to_token_counter: &Counter<String>,
+    combination_queue: Vec<TokenPair<'a>>,
+) -> Vec<TokenPair<'a>> {
     // A list of matches sort from worst to best. The algorithm will
     // pop off the last value, getting the best possible unused token match.
     let mut sorted_combination_queue = combination_queue;
     sorted_combination_queue
-        .sort_unstable_by(|(val_a, _, _), (val_b, _, _)| val_a.partial_cmp(val_b).unwrap());
+        .sort_unstable_by(|a, b| a.weighted_similarity().total_cmp(&b.weighted_similarity()));
 
-    let mut score_in_common = 0.0;
+    let mut chosen = vec![];
     let mut from_tokens_used: Counter<String> = Counter::new();
     let mut to_tokens_used: Counter<String> = Counter::new();
 
-    while let Some((this_score, from_token, to_token)) = sorted_combination_queue.pop() {
-        score_in_common += this_score;
+    while let Some(pair) = sorted_combination_queue.pop() {
+        let (from_token, to_token) = (pair.from_token, pair.to_token);
+        chosen.push(pair);
 
         from_tokens_used[from_token] += 1;
         to_tokens_used[to_token] += 1;
 
         if from_tokens_used[from_token] == from_token_counter[from_token] {
-            sorted_combination_queue.retain(|(_, ft, _)| ft != &from_token);
+            sorted_combination_queue.retain(|p| p.from_token != from_token);
         }
 
         if to_tokens_used[to_token] == to_token_counter[to_token] {
-            sorted_combination_queue.retain(|(_, _, tt)| tt != &to_token);
+            sorted_combination_queue.retain(|p| p.to_token != to_token);
         }
     }
 
-    score_in_common / (from_norm * to_norm)
+    chosen
 }
@@ -2,18 +2,21 @@ use std::cmp::min;
 use std::collections::BTreeMap;
 
 use getset::Getters;
+use serde::{Deserialize, Serialize};
 
 use crate::core::idf::Idf;
 use crate::name::base::{NameContainer, NameProcessed};
+use crate::name::score::{PairExplanation, ScoreExplanation};
 
 /*****************************************************************************/
 /* Weighted name for exact token matching                                    */
 /*****************************************************************************/
 /// A weighted Name suitable for matching
-#[derive(Debug, Getters)]
+#[derive(Debug, Getters, Serialize, Deserialize)]
 pub struct NameWeighted<N> {
     #[getset(get = "pub")]
     name: N,
+    legal_form: Option<String>,
     #[getset(get = "pub")]
     token_count_weights: BTreeMap<String, (usize, f64)>,
     #[getset(get = "pub")]
@@ -24,6 +27,10 @@ impl<N> NameContainer<N> for NameWeighted<N> {
     fn get_name(&self) -> &N {
         &self.name
     }
+
+    fn legal_form(&self) -> Option<&str> {
+        self.legal_form.as_deref()
+    }
 }
 
 impl<N> NameWeighted<N> {
@@ -40,6 +47,7 @@ impl<N> NameWeighted<N> {
 
         NameWeighted {
             name: np.name,
+            legal_form: np.legal_form,
             token_count_weights,
             norm: total_weight.sqrt(),
         }
@@ -61,4 +69,30 @@ impl<N> NameWeighted<N> {
 
         score_in_common / (self.norm * to_name.norm)
     }
+
+    /// Explain the score given by `compute_match_score`. Each shared token is
+    /// paired with itself once for each occurrence in both names.
+    pub fn explain_match_score(&self, to_name: &Self) -> ScoreExplanation {
+        let mut pairs = vec![];
+        for (token, (count_in_from, weight)) in self.token_count_weights() {
+            if let Some((count_in_to, _)) = to_name.token_count_weights().get(token) {
+                for _ in 0..min(*count_in_from, *count_in_to) {
+                    pairs.push(PairExplanation {
+                        from_token: token.to_string(),
+                        to_token: token.to_string(),
+                        similarity: 1.0,
+                        from_weight: *weight,
+                        to_weight: *weight,
+                    });
+                }
+            }
+        }
+
+        ScoreExplanation {
+            pairs,
+            from_norm: self.norm,
+            to_norm: to_name.norm,
+            score: self.compute_match_score(to_name),
+        }
+    }
 }
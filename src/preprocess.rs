@@ -1,9 +1,18 @@
+pub mod company;
+pub mod phonetic;
+pub mod stopwords;
+pub mod substitutions;
+
+use std::collections::HashSet;
+
 use deunicode::deunicode;
 use rayon::prelude::*;
 
 use crate::cli::PreprocessingOptions;
 use crate::name::base::UnprocessedName;
 use crate::name::NameProcessed;
+use crate::preprocess::company::LegalForm;
+use crate::preprocess::phonetic::PhoneticAlgorithm;
 
 pub fn prep_names<N>(names: Vec<N>, prep_opts: &PreprocessingOptions) -> Vec<NameProcessed<N>>
 where
@@ -15,28 +24,100 @@ where
         .collect()
 }
 
-pub fn prep_name<N: UnprocessedName>(name: N, prep_opts: &PreprocessingOptions) -> NameProcessed<N> {
-    let tokens = prep_words(name.unprocessed_name(), prep_opts);
+pub fn prep_name<N: UnprocessedName>(
+    name: N,
+    prep_opts: &PreprocessingOptions,
+) -> NameProcessed<N> {
+    let (tokens, legal_form) = prep_words_with_legal_form(name.unprocessed_name(), prep_opts);
 
-    NameProcessed::new(name, tokens)
+    NameProcessed::new(name, tokens).with_legal_form(legal_form)
 }
 
 pub fn prep_words(source_string: &str, opts: &PreprocessingOptions) -> Vec<String> {
-    source_string
-        .split_ascii_whitespace()
-        .map(|word| {
+    prep_words_with_legal_form(source_string, opts).0
+}
+
+/// The tokens of a name, along with the canonical legal form ending it when the
+/// company profile separates legal forms from names
+pub fn prep_words_with_legal_form(
+    source_string: &str,
+    opts: &PreprocessingOptions,
+) -> (Vec<String>, Option<String>) {
+    let mut words: Vec<&str> = match opts.person {
+        true => source_string
+            .split(|c: char| c.is_ascii_whitespace() || c == ',' || c == '.')
+            .filter(|w| !w.is_empty())
+            .collect(),
+        false => source_string.split_ascii_whitespace().collect(),
+    };
+    if !opts.substitutions.is_empty() {
+        let forms: Vec<String> = words.iter().map(|w| match_form(w, opts)).collect();
+        words = opts.substitutions.apply(&words, &forms);
+    }
+
+    let mut legal_form = None;
+    if opts.company {
+        let forms: Vec<String> = words.iter().map(|w| match_form(w, opts)).collect();
+        let (start, canonical) = opts.legal_forms.replace_suffix(&forms);
+        words.truncate(start);
+        match opts.legal_form {
+            LegalForm::Keep => words.extend(canonical),
+            LegalForm::Separate if !canonical.is_empty() => legal_form = Some(canonical.join(" ")),
+            LegalForm::Separate | LegalForm::Drop => {}
+        }
+    }
+
+    let words: Vec<PrepString> = words.iter().map(|word| normalise(word, opts)).collect();
+    let tokens = remove_stopwords(words, &opts.stopwords)
+        .into_iter()
+        .flat_map(|word| {
+            word.phonetic(opts.phonetic_algorithm())
+                .into_iter()
+                .map(|ps| ps.trim_length(opts.token_length).0)
+        })
+        .collect();
+    (tokens, legal_form)
+}
+
+/// A word as it is compared against substitution phrases: normalised, unless
+/// that leaves nothing, as for "&", in which case only unicode conversion and
+/// lowercasing apply.
+pub fn match_form(word: &str, opts: &PreprocessingOptions) -> String {
+    match normalise(word, opts).0 {
+        form if form.is_empty() => {
             PrepString(word.to_string())
                 .deunicode(!opts.retain_unicode)
                 .ascii_lowercase(!opts.case_sensitive)
-                .filter_alphabetic(!opts.retain_non_alphabetic)
-                .soundex(opts.soundex)
-                .trim_length(opts.token_length)
                 .0
-        })
+        }
+        form => form,
+    }
+}
+
+/// A word as it is compared against stopwords, before phonetic coding and trimming
+pub fn normalise_word(word: &str, opts: &PreprocessingOptions) -> String {
+    normalise(word, opts).0
+}
+
+fn normalise(word: &str, opts: &PreprocessingOptions) -> PrepString {
+    PrepString(word.to_string())
+        .deunicode(!opts.retain_unicode)
+        .ascii_lowercase(!opts.case_sensitive)
+        .filter_alphabetic(!opts.retain_non_alphabetic)
+}
+
+/// Drop stopwords, unless every word is one, so that no name is left empty
+fn remove_stopwords(words: Vec<PrepString>, stopwords: &HashSet<String>) -> Vec<PrepString> {
+    if words.iter().all(|w| stopwords.contains(&w.0)) {
+        return words;
+    }
+    words
+        .into_iter()
+        .filter(|w| !stopwords.contains(&w.0))
         .collect()
 }
 
-/// A newtype that allows for nicer chaning of functions during text preprocessing
+/// A newtype that allows for nicer chaining of functions during text preprocessing
 struct PrepString(String);
 
 impl PrepString {
@@ -64,11 +145,12 @@ impl PrepString {
         }
     }
 
-    fn soundex(self, execute: bool) -> Self {
-        if execute {
-            PrepString(soundex::american_soundex(&self.0))
-        } else {
-            self
+    /// Phonetic encoding may produce more than one code for a word, each of which
+    /// becomes a token.
+    fn phonetic(self, algorithm: Option<PhoneticAlgorithm>) -> Vec<Self> {
+        match algorithm {
+            Some(a) => a.encode(&self.0).into_iter().map(PrepString).collect(),
+            None => vec![self],
         }
     }
 
@@ -0,0 +1,157 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Spellings of legal forms across jurisdictions, each with its canonical form.
+/// Spellings are compared ignoring case, accents and punctuation, so "Inc." and
+/// "S.A." are covered by "inc" and "sa".
+pub const LEGAL_FORMS: &[(&str, &str)] = &[
+    ("inc", "inc"),
+    ("incorporated", "inc"),
+    ("corp", "corp"),
+    ("corporation", "corp"),
+    ("co", "co"),
+    ("company", "co"),
+    ("ltd", "ltd"),
+    ("limited", "ltd"),
+    ("llc", "llc"),
+    ("l l c", "llc"),
+    ("limited liability company", "llc"),
+    ("llp", "llp"),
+    ("limited liability partnership", "llp"),
+    ("lp", "lp"),
+    ("limited partnership", "lp"),
+    ("plc", "plc"),
+    ("public limited company", "plc"),
+    ("pty", "pty"),
+    ("proprietary", "pty"),
+    ("gmbh", "gmbh"),
+    ("gesellschaft mit beschrankter haftung", "gmbh"),
+    ("ag", "ag"),
+    ("aktiengesellschaft", "ag"),
+    ("kg", "kg"),
+    ("kommanditgesellschaft", "kg"),
+    ("sa", "sa"),
+    ("s a", "sa"),
+    ("societe anonyme", "sa"),
+    ("sociedad anonima", "sa"),
+    ("sarl", "sarl"),
+    ("s a r l", "sarl"),
+    ("sas", "sas"),
+    ("s a s", "sas"),
+    ("spa", "spa"),
+    ("s p a", "spa"),
+    ("societa per azioni", "spa"),
+    ("srl", "srl"),
+    ("s r l", "srl"),
+    ("nv", "nv"),
+    ("n v", "nv"),
+    ("naamloze vennootschap", "nv"),
+    ("bv", "bv"),
+    ("b v", "bv"),
+    ("besloten vennootschap", "bv"),
+    ("ab", "ab"),
+    ("aktiebolag", "ab"),
+    ("oy", "oy"),
+    ("oyj", "oyj"),
+    ("kk", "kk"),
+    ("kabushiki kaisha", "kk"),
+];
+
+/// What the company profile does with the legal form ending a name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LegalForm {
+    /// Keep the canonical legal form among the name's tokens
+    #[default]
+    Keep,
+    /// Remove the legal form from the tokens, using it only to rank equally
+    /// scored matches, preferring to names with the same legal form
+    Separate,
+    /// Remove the legal form
+    Drop,
+}
+
+impl LegalForm {
+    pub const VARIANTS: &'static [&'static str] = &["keep", "separate", "drop"];
+}
+
+impl FromStr for LegalForm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(LegalForm::Keep),
+            "separate" => Ok(LegalForm::Separate),
+            "drop" => Ok(LegalForm::Drop),
+            _ => Err(format!(
+                "unknown legal form handling {}, expected one of {}",
+                s,
+                LegalForm::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LegalForm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LegalForm::Keep => "keep",
+            LegalForm::Separate => "separate",
+            LegalForm::Drop => "drop",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cli::PreprocessingOptions;
+    use crate::preprocess::{prep_words, prep_words_with_legal_form};
+
+    #[test]
+    fn legal_forms_canonicalised() {
+        let opts = PreprocessingOptions {
+            company: true,
+            ..PreprocessingOptions::default()
+        }
+        .load_word_lists()
+        .unwrap();
+
+        for name in ["Apple Inc.", "APPLE INCORPORATED", "Apple, Inc"] {
+            assert_eq!(prep_words(name, &opts), vec!["apple", "inc"]);
+        }
+        assert_eq!(prep_words("Nestlé S. A.", &opts), vec!["nestle", "sa"]);
+        assert_eq!(
+            prep_words("Samsung Co., Limited", &opts),
+            vec!["samsung", "co", "ltd"]
+        );
+        // Only a legal form ending the name is canonicalised
+        assert_eq!(
+            prep_words("Limited Brands Corporation", &opts),
+            vec!["limited", "brands", "corp"]
+        );
+        assert_eq!(prep_words("Company", &opts), vec!["company"]);
+
+        let opts = PreprocessingOptions {
+            legal_form: LegalForm::Separate,
+            ..opts
+        };
+        assert_eq!(
+            prep_words_with_legal_form("Siemens Aktiengesellschaft", &opts),
+            (vec!["siemens".to_string()], Some("ag".to_string()))
+        );
+        let opts = PreprocessingOptions {
+            legal_form: LegalForm::Drop,
+            ..opts
+        };
+        assert_eq!(
+            prep_words_with_legal_form("Siemens AG", &opts),
+            (vec!["siemens".to_string()], None)
+        );
+    }
+}
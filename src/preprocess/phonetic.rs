@@ -0,0 +1,722 @@
+mod daitch_mokotoff;
+mod double_metaphone;
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Phonetic algorithms available for encoding tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PhoneticAlgorithm {
+    /// American Soundex
+    Soundex,
+    /// Refined Soundex, which keeps vowel positions and finer consonant classes
+    RefinedSoundex,
+    /// Original Metaphone
+    Metaphone,
+    /// Double Metaphone, keeping only the primary code
+    DoubleMetaphone,
+    /// New York State Identification and Intelligence System
+    Nysiis,
+    /// Caverphone 2.0
+    Caverphone,
+    /// Daitch-Mokotoff Soundex, producing every code of an ambiguous spelling
+    DaitchMokotoff,
+}
+
+impl PhoneticAlgorithm {
+    pub const VARIANTS: &'static [&'static str] = &[
+        "soundex",
+        "refined-soundex",
+        "metaphone",
+        "double-metaphone",
+        "nysiis",
+        "caverphone",
+        "daitch-mokotoff",
+    ];
+
+    /// Encode a word. Double Metaphone returns only its primary code, since an
+    /// alternate code kept as a second token would count against an exact match
+    /// and weigh twice in the norm of the name. Daitch-Mokotoff returns every
+    /// distinct code of an ambiguous spelling. Empty codes, as Metaphone gives
+    /// "WH" and Double Metaphone gives an initial "W", are left out, so that such
+    /// words cannot match each other.
+    pub fn encode(self, word: &str) -> Vec<String> {
+        let codes = match self {
+            PhoneticAlgorithm::Soundex => vec![soundex::american_soundex(word)],
+            PhoneticAlgorithm::RefinedSoundex => vec![refined_soundex(word)],
+            PhoneticAlgorithm::Metaphone => vec![metaphone(word)],
+            PhoneticAlgorithm::DoubleMetaphone => vec![double_metaphone::double_metaphone(word).0],
+            PhoneticAlgorithm::Nysiis => vec![nysiis(word)],
+            PhoneticAlgorithm::Caverphone => vec![caverphone(word)],
+            PhoneticAlgorithm::DaitchMokotoff => daitch_mokotoff::daitch_mokotoff(word),
+        };
+        codes.into_iter().filter(|c| !c.is_empty()).collect()
+    }
+}
+
+impl FromStr for PhoneticAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "soundex" => Ok(PhoneticAlgorithm::Soundex),
+            "refined-soundex" => Ok(PhoneticAlgorithm::RefinedSoundex),
+            "metaphone" => Ok(PhoneticAlgorithm::Metaphone),
+            "double-metaphone" => Ok(PhoneticAlgorithm::DoubleMetaphone),
+            "nysiis" => Ok(PhoneticAlgorithm::Nysiis),
+            "caverphone" => Ok(PhoneticAlgorithm::Caverphone),
+            "daitch-mokotoff" => Ok(PhoneticAlgorithm::DaitchMokotoff),
+            _ => Err(format!(
+                "unknown phonetic algorithm {}, expected one of {}",
+                s,
+                PhoneticAlgorithm::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PhoneticAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PhoneticAlgorithm::Soundex => "soundex",
+            PhoneticAlgorithm::RefinedSoundex => "refined-soundex",
+            PhoneticAlgorithm::Metaphone => "metaphone",
+            PhoneticAlgorithm::DoubleMetaphone => "double-metaphone",
+            PhoneticAlgorithm::Nysiis => "nysiis",
+            PhoneticAlgorithm::Caverphone => "caverphone",
+            PhoneticAlgorithm::DaitchMokotoff => "daitch-mokotoff",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The ASCII letters of a word in upper case
+fn upper_ascii_letters(word: &str) -> Vec<char> {
+    word.chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/*****************************************************************************/
+/* Refined Soundex                                                           */
+/*****************************************************************************/
+fn refined_soundex_code(c: char) -> char {
+    match c {
+        'B' | 'P' => '1',
+        'F' | 'V' => '2',
+        'C' | 'K' | 'S' => '3',
+        'G' | 'J' => '4',
+        'Q' | 'X' | 'Z' => '5',
+        'D' | 'T' => '6',
+        'L' => '7',
+        'M' | 'N' => '8',
+        'R' => '9',
+        _ => '0',
+    }
+}
+
+fn refined_soundex(word: &str) -> String {
+    let letters = upper_ascii_letters(word);
+    let mut code = String::new();
+    if let Some(first) = letters.first() {
+        code.push(*first);
+    }
+
+    let mut last = None;
+    for c in letters {
+        let current = refined_soundex_code(c);
+        if last != Some(current) {
+            code.push(current);
+            last = Some(current);
+        }
+    }
+    code
+}
+
+/*****************************************************************************/
+/* Metaphone                                                                 */
+/*****************************************************************************/
+const METAPHONE_MAX_LENGTH: usize = 4;
+
+fn metaphone(word: &str) -> String {
+    let letters = upper_ascii_letters(word);
+    if letters.len() <= 1 {
+        return letters.into_iter().collect();
+    }
+
+    // Handle exceptions in the first two letters
+    let w: Vec<char> = match (letters[0], letters[1]) {
+        ('K', 'N') | ('G', 'N') | ('P', 'N') | ('A', 'E') | ('W', 'R') => letters[1..].to_vec(),
+        ('W', 'H') => {
+            let mut w = letters[1..].to_vec();
+            w[0] = 'W';
+            w
+        }
+        ('X', _) => {
+            let mut w = letters;
+            w[0] = 'S';
+            w
+        }
+        _ => letters,
+    };
+
+    let len = w.len();
+    let at = |i: usize| -> Option<char> { w.get(i).copied() };
+    let is_vowel = |i: usize| matches!(at(i), Some('A' | 'E' | 'I' | 'O' | 'U'));
+    let is_front_vowel = |i: usize| matches!(at(i), Some('E' | 'I' | 'Y'));
+    let previous_is = |i: usize, c: char| i > 0 && at(i - 1) == Some(c);
+    let next_is = |i: usize, c: char| at(i + 1) == Some(c);
+    let is_last = |i: usize| i + 1 == len;
+    let region = |i: usize, s: &str| {
+        let s: Vec<char> = s.chars().collect();
+        i + s.len() <= len && w[i..i + s.len()] == s[..]
+    };
+
+    let mut code = String::new();
+    let mut n = 0;
+    while code.len() < METAPHONE_MAX_LENGTH && n < len {
+        let symbol = w[n];
+
+        // Skip duplicate letters except C
+        if symbol != 'C' && previous_is(n, symbol) {
+            n += 1;
+            continue;
+        }
+
+        match symbol {
+            'A' | 'E' | 'I' | 'O' | 'U' if n == 0 => code.push(symbol),
+            // Silent in a final MB
+            'B' if !(previous_is(n, 'M') && is_last(n)) => code.push('B'),
+            'C' => {
+                if previous_is(n, 'S') && !is_last(n) && is_front_vowel(n + 1) {
+                    // Silent in SCI, SCE, SCY
+                } else if region(n, "CIA") {
+                    code.push('X')
+                } else if !is_last(n) && is_front_vowel(n + 1) {
+                    code.push('S')
+                } else if previous_is(n, 'S') && next_is(n, 'H') {
+                    code.push('K')
+                } else if next_is(n, 'H') {
+                    if n == 0 && len >= 3 && is_vowel(2) {
+                        code.push('K')
+                    } else {
+                        code.push('X')
+                    }
+                } else {
+                    code.push('K')
+                }
+            }
+            'D' => {
+                if next_is(n, 'G') && is_front_vowel(n + 2) {
+                    code.push('J');
+                    n += 2;
+                } else {
+                    code.push('T')
+                }
+            }
+            'G' => {
+                let silent = (next_is(n, 'H') && (n + 2 == len || !is_vowel(n + 2)))
+                    || (n > 0 && region(n, "GN"));
+                if !silent {
+                    if is_front_vowel(n + 1) && !previous_is(n, 'G') {
+                        code.push('J')
+                    } else {
+                        code.push('K')
+                    }
+                }
+            }
+            'H' => {
+                let after_varson = n > 0 && matches!(at(n - 1), Some('C' | 'S' | 'P' | 'T' | 'G'));
+                if !is_last(n) && !after_varson && is_vowel(n + 1) {
+                    code.push('H')
+                }
+            }
+            'F' | 'J' | 'L' | 'M' | 'N' | 'R' => code.push(symbol),
+            'K' if !previous_is(n, 'C') => code.push('K'),
+            'P' => {
+                if next_is(n, 'H') {
+                    code.push('F')
+                } else {
+                    code.push('P')
+                }
+            }
+            'Q' => code.push('K'),
+            'S' => {
+                if region(n, "SH") || region(n, "SIO") || region(n, "SIA") {
+                    code.push('X')
+                } else {
+                    code.push('S')
+                }
+            }
+            'T' => {
+                if region(n, "TIA") || region(n, "TIO") {
+                    code.push('X')
+                } else if region(n, "TCH") {
+                    // Silent in TCH
+                } else if region(n, "TH") {
+                    code.push('0')
+                } else {
+                    code.push('T')
+                }
+            }
+            'V' => code.push('F'),
+            // Silent unless followed by a vowel
+            'W' | 'Y' if !is_last(n) && is_vowel(n + 1) => code.push(symbol),
+            'X' => code.push_str("KS"),
+            'Z' => code.push('S'),
+            _ => {}
+        }
+        n += 1;
+    }
+
+    code.truncate(METAPHONE_MAX_LENGTH);
+    code
+}
+
+/*****************************************************************************/
+/* NYSIIS                                                                    */
+/*****************************************************************************/
+const NYSIIS_MAX_LENGTH: usize = 6;
+
+fn nysiis(word: &str) -> String {
+    let mut w: String = upper_ascii_letters(word).into_iter().collect();
+    if w.is_empty() {
+        return w;
+    }
+
+    // Translate the first characters of the name
+    for (from, to) in &[
+        ("MAC", "MCC"),
+        ("KN", "NN"),
+        ("K", "C"),
+        ("PH", "FF"),
+        ("PF", "FF"),
+        ("SCH", "SSS"),
+    ] {
+        if let Some(rest) = w.strip_prefix(from) {
+            w = format!("{}{}", to, rest);
+            break;
+        }
+    }
+
+    // Translate the last characters of the name
+    for (from, to) in &[
+        ("EE", "Y"),
+        ("IE", "Y"),
+        ("DT", "D"),
+        ("RT", "D"),
+        ("RD", "D"),
+        ("NT", "D"),
+        ("ND", "D"),
+    ] {
+        if let Some(rest) = w.strip_suffix(from) {
+            w = format!("{}{}", rest, to);
+            break;
+        }
+    }
+
+    let is_vowel = |c: char| matches!(c, 'A' | 'E' | 'I' | 'O' | 'U');
+    let mut chars: Vec<char> = w.chars().collect();
+    let len = chars.len();
+    let mut key = vec![chars[0]];
+
+    for i in 1..len {
+        let previous = chars[i - 1];
+        let current = chars[i];
+        let next = chars.get(i + 1).copied().unwrap_or(' ');
+        let after_next = chars.get(i + 2).copied().unwrap_or(' ');
+
+        let transcoded: Vec<char> = match current {
+            'E' if next == 'V' => vec!['A', 'F'],
+            c if is_vowel(c) => vec!['A'],
+            'Q' => vec!['G'],
+            'Z' => vec!['S'],
+            'M' => vec!['N'],
+            'K' if next == 'N' => vec!['N', 'N'],
+            'K' => vec!['C'],
+            'S' if next == 'C' && after_next == 'H' => vec!['S', 'S', 'S'],
+            'P' if next == 'H' => vec!['F', 'F'],
+            'H' if !is_vowel(previous) || !is_vowel(next) => vec![previous],
+            'W' if is_vowel(previous) => vec![previous],
+            c => vec![c],
+        };
+        for (j, c) in transcoded.into_iter().enumerate() {
+            chars[i + j] = c;
+        }
+
+        if chars[i] != chars[i - 1] {
+            key.push(chars[i]);
+        }
+    }
+
+    if key.len() > 1 {
+        if key.last() == Some(&'S') {
+            key.pop();
+        }
+        if key.len() > 2 && key.ends_with(&['A', 'Y']) {
+            key.remove(key.len() - 2);
+        }
+        if key.last() == Some(&'A') {
+            key.pop();
+        }
+    }
+
+    key.into_iter().take(NYSIIS_MAX_LENGTH).collect()
+}
+
+/*****************************************************************************/
+/* Caverphone                                                                */
+/*****************************************************************************/
+const CAVERPHONE_LENGTH: usize = 10;
+
+fn caverphone(word: &str) -> String {
+    let mut w: String = upper_ascii_letters(word)
+        .into_iter()
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    fn replace_start(w: &mut String, from: &str, to: &str) {
+        if let Some(rest) = w.strip_prefix(from) {
+            *w = format!("{}{}", to, rest);
+        }
+    }
+    fn replace_end(w: &mut String, from: &str, to: &str) {
+        if let Some(rest) = w.strip_suffix(from) {
+            *w = format!("{}{}", rest, to);
+        }
+    }
+    fn replace_all(w: &mut String, from: &str, to: &str) {
+        *w = w.replace(from, to);
+    }
+    fn replace_runs(w: &mut String, from: char, to: char) {
+        let mut replaced = String::with_capacity(w.len());
+        let mut in_run = false;
+        for c in w.chars() {
+            if c != from {
+                replaced.push(c);
+                in_run = false;
+            } else if !in_run {
+                replaced.push(to);
+                in_run = true;
+            }
+        }
+        *w = replaced;
+    }
+
+    replace_end(&mut w, "e", "");
+    for (from, to) in &[
+        ("cough", "cou2f"),
+        ("rough", "rou2f"),
+        ("tough", "tou2f"),
+        ("enough", "enou2f"),
+        ("trough", "trou2f"),
+        ("gn", "2n"),
+    ] {
+        replace_start(&mut w, from, to);
+    }
+    replace_end(&mut w, "mb", "m2");
+    for (from, to) in &[
+        ("cq", "2q"),
+        ("ci", "si"),
+        ("ce", "se"),
+        ("cy", "sy"),
+        ("tch", "2ch"),
+        ("c", "k"),
+        ("q", "k"),
+        ("x", "k"),
+        ("v", "f"),
+        ("dg", "2g"),
+        ("tio", "sio"),
+        ("tia", "sia"),
+        ("d", "t"),
+        ("ph", "fh"),
+        ("b", "p"),
+        ("sh", "s2"),
+        ("z", "s"),
+    ] {
+        replace_all(&mut w, from, to);
+    }
+
+    if w.starts_with(['a', 'e', 'i', 'o', 'u']) {
+        w.replace_range(0..1, "A");
+    }
+    for vowel in &["a", "e", "i", "o", "u"] {
+        replace_all(&mut w, vowel, "3");
+    }
+    replace_all(&mut w, "j", "y");
+    replace_start(&mut w, "y3", "Y3");
+    replace_start(&mut w, "y", "A");
+    replace_all(&mut w, "y", "3");
+    replace_all(&mut w, "3gh3", "3kh3");
+    replace_all(&mut w, "gh", "22");
+    replace_all(&mut w, "g", "k");
+    for (from, to) in &[
+        ('s', 'S'),
+        ('t', 'T'),
+        ('p', 'P'),
+        ('k', 'K'),
+        ('f', 'F'),
+        ('m', 'M'),
+        ('n', 'N'),
+    ] {
+        replace_runs(&mut w, *from, *to);
+    }
+    replace_all(&mut w, "w3", "W3");
+    replace_all(&mut w, "wh3", "Wh3");
+    replace_end(&mut w, "w", "3");
+    replace_all(&mut w, "w", "2");
+    replace_start(&mut w, "h", "A");
+    replace_all(&mut w, "h", "2");
+    replace_all(&mut w, "r3", "R3");
+    replace_end(&mut w, "r", "3");
+    replace_all(&mut w, "r", "2");
+    replace_all(&mut w, "l3", "L3");
+    replace_end(&mut w, "l", "3");
+    replace_all(&mut w, "l", "2");
+    replace_all(&mut w, "2", "");
+    replace_end(&mut w, "3", "A");
+    replace_all(&mut w, "3", "");
+
+    w.push_str(&"1".repeat(CAVERPHONE_LENGTH));
+    w.truncate(CAVERPHONE_LENGTH);
+    w
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cli::PreprocessingOptions;
+    use crate::preprocess::prep_words;
+
+    #[test]
+    fn refined_soundex_codes() {
+        assert_eq!(refined_soundex("testing"), "T6036084");
+        assert_eq!(refined_soundex("The"), "T60");
+        assert_eq!(refined_soundex("quick"), "Q503");
+        assert_eq!(refined_soundex("brown"), "B1908");
+    }
+
+    #[test]
+    fn metaphone_codes() {
+        for (word, code) in &[
+            ("howl", "HL"),
+            ("testing", "TSTN"),
+            ("the", "0"),
+            ("quick", "KK"),
+            ("brown", "BRN"),
+            ("fox", "FKS"),
+            ("jumped", "JMPT"),
+            ("over", "OFR"),
+            ("lazy", "LS"),
+            ("dogs", "TKS"),
+            ("knight", "NT"),
+            ("wright", "RT"),
+        ] {
+            assert_eq!(metaphone(word), *code, "metaphone of {}", word);
+        }
+    }
+
+    #[test]
+    fn nysiis_codes() {
+        for (word, code) in &[
+            ("brian", "BRAN"),
+            ("brown", "BRAN"),
+            ("brun", "BRAN"),
+            ("mitchell", "MATCAL"),
+            ("knight", "NAGT"),
+            ("macintosh", "MCANT"),
+        ] {
+            assert_eq!(nysiis(word), *code, "nysiis of {}", word);
+        }
+    }
+
+    #[test]
+    fn caverphone_codes() {
+        for (word, code) in &[
+            ("peter", "PTA1111111"),
+            ("thompson", "TMPSN11111"),
+            ("lee", "LA11111111"),
+            ("stevenson", "STFNSN1111"),
+        ] {
+            assert_eq!(caverphone(word), *code, "caverphone of {}", word);
+        }
+    }
+
+    /// Vectors from the test suites of Apache Commons Codec, whose encoders
+    /// these follow
+    #[test]
+    fn commons_codec_reference_vectors() {
+        for (word, code) in &[
+            ("robert", "R163"),
+            ("rupert", "R163"),
+            ("rubin", "R150"),
+            ("ashcraft", "A261"),
+            ("ashcroft", "A261"),
+            ("tymczak", "T522"),
+            ("pfister", "P236"),
+            ("gutierrez", "G362"),
+            ("jackson", "J250"),
+            ("lee", "L000"),
+        ] {
+            assert_eq!(
+                PhoneticAlgorithm::Soundex.encode(word),
+                vec![*code],
+                "soundex of {}",
+                word
+            );
+        }
+
+        for (word, code) in &[
+            ("jumped", "J408106"),
+            ("over", "O0209"),
+            ("lazy", "L7050"),
+            ("dogs", "D6043"),
+            ("fox", "F205"),
+        ] {
+            assert_eq!(refined_soundex(word), *code, "refined soundex of {}", word);
+        }
+
+        for (word, code) in &[
+            ("schedule", "SKTL"),
+            ("schematic", "SKMT"),
+            ("character", "KRKT"),
+            ("teach", "TX"),
+            ("dodgy", "TJ"),
+            ("dodge", "TJ"),
+            ("adgiemti", "AJMT"),
+            ("science", "SNS"),
+            ("scene", "SN"),
+            ("scy", "S"),
+            ("why", ""),
+            ("phish", "FX"),
+            ("shot", "XT"),
+            ("odsian", "OTXN"),
+            ("pulsion", "PLXN"),
+            ("otia", "OX"),
+            ("portion", "PRXN"),
+            ("retch", "RX"),
+            ("watch", "WX"),
+            ("axeaxe", "AKSK"),
+        ] {
+            assert_eq!(metaphone(word), *code, "metaphone of {}", word);
+        }
+
+        for (word, code) in &[
+            ("capp", "CAP"),
+            ("cope", "CAP"),
+            ("copp", "CAP"),
+            ("kipp", "CAP"),
+            ("dane", "DAN"),
+            ("dean", "DAN"),
+            ("dionne", "DAN"),
+            ("phil", "FAL"),
+            ("trueman", "TRANAN"),
+            ("truman", "TRANAN"),
+            ("knuth", "NAT"),
+            ("koehn", "CAN"),
+            ("mckee", "MCY"),
+            ("mackie", "MCY"),
+            ("bart", "BAD"),
+            ("hurd", "HAD"),
+            ("hunt", "HAD"),
+            ("vasquez", "VASG"),
+            ("frazier", "FRASAR"),
+            ("bowman", "BANAN"),
+            ("rickert", "RACAD"),
+            ("deutsch", "DAT"),
+            ("shriver", "SRAVAR"),
+            ("kuhl", "CAL"),
+            ("rawson", "RASAN"),
+            ("jiles", "JAL"),
+            ("carraway", "CARY"),
+            ("yamada", "YANAD"),
+            ("pfeister", "FASTAR"),
+        ] {
+            assert_eq!(nysiis(word), *code, "nysiis of {}", word);
+        }
+
+        for (word, code) in &[
+            ("ready", "RTA1111111"),
+            ("social", "SSA1111111"),
+            ("able", "APA1111111"),
+            ("tedder", "TTA1111111"),
+            ("karleen", "KLN1111111"),
+            ("dyun", "TN11111111"),
+            ("add", "AT11111111"),
+            ("aid", "AT11111111"),
+            ("at", "AT11111111"),
+            ("art", "AT11111111"),
+            ("eat", "AT11111111"),
+            ("earth", "AT11111111"),
+            ("head", "AT11111111"),
+            ("hit", "AT11111111"),
+            ("hot", "AT11111111"),
+            ("hold", "AT11111111"),
+            ("hard", "AT11111111"),
+            ("heart", "AT11111111"),
+            ("it", "AT11111111"),
+            ("out", "AT11111111"),
+            ("old", "AT11111111"),
+            ("darda", "TTA1111111"),
+            ("deirdre", "TTA1111111"),
+            ("dodie", "TTA1111111"),
+            ("doherty", "TTA1111111"),
+            ("teddy", "TTA1111111"),
+            ("theda", "TTA1111111"),
+            ("mb", "M111111111"),
+            ("mbmb", "MPM1111111"),
+        ] {
+            assert_eq!(caverphone(word), *code, "caverphone of {}", word);
+        }
+    }
+
+    #[test]
+    fn multiple_codes() {
+        // Double Metaphone keeps only the primary code
+        assert_eq!(
+            PhoneticAlgorithm::DoubleMetaphone.encode("smith"),
+            vec!["SM0"]
+        );
+        assert_eq!(
+            PhoneticAlgorithm::DoubleMetaphone.encode("schmidt"),
+            vec!["XMT"]
+        );
+        assert_eq!(
+            PhoneticAlgorithm::DaitchMokotoff.encode("peters"),
+            vec!["739400", "734000"]
+        );
+        assert_eq!(PhoneticAlgorithm::Soundex.encode("smith"), vec!["S530"]);
+    }
+
+    #[test]
+    fn empty_codes_dropped() {
+        assert!(PhoneticAlgorithm::DoubleMetaphone.encode("w").is_empty());
+        assert!(PhoneticAlgorithm::DoubleMetaphone.encode("h").is_empty());
+        assert!(PhoneticAlgorithm::Metaphone.encode("wh").is_empty());
+        assert!(PhoneticAlgorithm::Nysiis.encode("as").is_empty());
+
+        // A single-letter initial no longer leaves a token shared by every name
+        let opts = PreprocessingOptions {
+            phonetic: Some(PhoneticAlgorithm::DoubleMetaphone),
+            ..PreprocessingOptions::default()
+        };
+        assert_eq!(prep_words("W Smith", &opts), vec!["SM0"]);
+        assert_eq!(prep_words("W Adams", &opts), vec!["ATMS"]);
+    }
+
+    #[test]
+    fn algorithm_names() {
+        for name in PhoneticAlgorithm::VARIANTS {
+            let algorithm: PhoneticAlgorithm = name.parse().unwrap();
+            assert_eq!(&algorithm.to_string(), name);
+        }
+        assert!("metafone".parse::<PhoneticAlgorithm>().is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use std::fs;
+
+use anyhow::Context;
+
+/// Common English function words
+pub const ENGLISH: &[&str] = &[
+    "a", "an", "and", "as", "at", "by", "for", "from", "in", "into", "of", "on", "or", "the", "to",
+    "with",
+];
+
+/// Words marking the legal form of a business entity
+pub const LEGAL: &[&str] = &[
+    "ag",
+    "bv",
+    "co",
+    "company",
+    "corp",
+    "corporation",
+    "gmbh",
+    "inc",
+    "incorporated",
+    "llc",
+    "llp",
+    "lp",
+    "ltd",
+    "limited",
+    "nv",
+    "plc",
+    "pty",
+    "sa",
+    "sarl",
+    "spa",
+    "srl",
+];
+
+/// The words of a stopword list: a built-in list by name, or otherwise a file of
+/// whitespace separated words, where lines starting with # are comments.
+pub fn load_stopword_list(source: &str) -> anyhow::Result<Vec<String>> {
+    let words = match source {
+        "english" => ENGLISH.iter().map(|w| w.to_string()).collect(),
+        "legal" => LEGAL.iter().map(|w| w.to_string()).collect(),
+        path => fs::read_to_string(path)
+            .with_context(|| format!("accessing stopwords file {}", path))?
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(|line| line.split_ascii_whitespace())
+            .map(|w| w.to_string())
+            .collect(),
+    };
+    Ok(words)
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cli::PreprocessingOptions;
+    use crate::preprocess::prep_words;
+    use crate::testing::TempFile;
+
+    #[test]
+    fn stopword_lists() {
+        assert!(load_stopword_list("english")
+            .unwrap()
+            .contains(&"the".to_string()));
+        assert!(load_stopword_list("legal")
+            .unwrap()
+            .contains(&"llc".to_string()));
+
+        let file = TempFile::new("stopwords", "# Generic words\nholdings group\n\n  Trust\n");
+        let words = load_stopword_list(file.path()).unwrap();
+        assert_eq!(words, vec!["holdings", "group", "Trust"]);
+
+        assert!(load_stopword_list("missing-stopwords.txt").is_err());
+    }
+
+    #[test]
+    fn stopwords_removed_after_normalisation() {
+        let opts = PreprocessingOptions {
+            stopword_lists: vec!["english".to_string(), "legal".to_string()],
+            ..PreprocessingOptions::default()
+        }
+        .load_word_lists()
+        .unwrap();
+
+        assert_eq!(
+            prep_words("The Bank of América, Inc.", &opts),
+            vec!["bank", "america"]
+        );
+        assert_eq!(prep_words("The Company", &opts), vec!["the", "company"]);
+        assert!(opts.stopwords.contains("the"));
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use csv::ReaderBuilder;
+use serde::{Deserialize, Serialize};
+
+/// Phrases of one or more words, each replaced by a canonical form of zero or
+/// more words. Words are compared in their match form, given by `match_form`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Substitutions {
+    /// The phrases keyed by their first word, longest first
+    phrases: HashMap<String, Vec<Substitution>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Substitution {
+    phrase: Vec<String>,
+    replacement: Vec<String>,
+}
+
+impl Substitutions {
+    /// Replace `phrase`, given as the match forms of its words, with `replacement`.
+    /// A later replacement for the same phrase takes precedence.
+    pub fn insert(&mut self, phrase: Vec<String>, replacement: &str) {
+        let first = match phrase.first() {
+            Some(first) => first.clone(),
+            None => return,
+        };
+        let substitution = Substitution {
+            phrase,
+            replacement: replacement
+                .split_ascii_whitespace()
+                .map(|w| w.to_string())
+                .collect(),
+        };
+
+        let candidates = self.phrases.entry(first).or_default();
+        candidates.retain(|s| s.phrase != substitution.phrase);
+        candidates.push(substitution);
+        candidates.sort_by_key(|s| std::cmp::Reverse(s.phrase.len()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phrases.is_empty()
+    }
+
+    /// Replace the longest phrase starting at each word, scanning from the left.
+    /// `forms` holds the match form of each word.
+    pub fn apply<'a>(&'a self, words: &[&'a str], forms: &[String]) -> Vec<&'a str> {
+        let mut substituted = Vec::with_capacity(words.len());
+        let mut i = 0;
+        while i < words.len() {
+            match self.longest_match(forms, i) {
+                Some(s) => {
+                    substituted.extend(s.replacement.iter().map(|w| w.as_str()));
+                    i += s.phrase.len();
+                }
+                None => {
+                    substituted.push(words[i]);
+                    i += 1;
+                }
+            }
+        }
+        substituted
+    }
+
+    /// Find the run of phrases ending the words, scanning as `apply` does, and
+    /// return the position it starts at along with the replacements of its
+    /// phrases. A run taking up every word is ignored, giving the number of
+    /// words and no replacements.
+    pub fn replace_suffix(&self, forms: &[String]) -> (usize, Vec<&str>) {
+        let mut run_start = None;
+        let mut run = vec![];
+        let mut i = 0;
+        while i < forms.len() {
+            match self.longest_match(forms, i) {
+                Some(s) => {
+                    run_start.get_or_insert(i);
+                    run.extend(s.replacement.iter().map(|w| w.as_str()));
+                    i += s.phrase.len();
+                }
+                None => {
+                    run_start = None;
+                    run.clear();
+                    i += 1;
+                }
+            }
+        }
+
+        match run_start {
+            Some(start) if start > 0 => (start, run),
+            _ => (forms.len(), vec![]),
+        }
+    }
+
+    fn longest_match(&self, forms: &[String], i: usize) -> Option<&Substitution> {
+        self.phrases.get(&forms[i]).and_then(|candidates| {
+            candidates
+                .iter()
+                .find(|s| forms[i..].starts_with(&s.phrase))
+        })
+    }
+}
+
+/// Read the phrase and replacement pairs of a CSV file with two columns and no
+/// header row
+pub fn load_substitution_file(path: &str) -> anyhow::Result<Vec<(String, String)>> {
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(false)
+        .from_path(path)
+        .with_context(|| format!("accessing substitutions file {}", path))?;
+
+    rdr.records()
+        .map(|record| {
+            let record = record.with_context(|| format!("reading substitutions from {}", path))?;
+            match (record.get(0), record.get(1), record.len()) {
+                (Some(phrase), Some(replacement), 2) => {
+                    Ok((phrase.to_string(), replacement.to_string()))
+                }
+                _ => anyhow::bail!(
+                    "expected a phrase and its replacement on line {} of {}",
+                    record.position().map_or(0, |p| p.line()),
+                    path
+                ),
+            }
+        })
+        .collect()
+}
+
+/*****************************************************************************/
+/* Testing                                                                   */
+/*****************************************************************************/
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cli::PreprocessingOptions;
+    use crate::preprocess::prep_words;
+    use crate::testing::TempFile;
+
+    #[test]
+    fn substitutions_applied_before_phonetic_coding() {
+        let file = TempFile::new(
+            "substitutions",
+            "corp,corporation\nintl,international\n&,and\nint'l business machines,ibm\nbros,brothers\n",
+        );
+        let opts = PreprocessingOptions {
+            substitution_files: vec![file.path().to_string()],
+            ..PreprocessingOptions::default()
+        }
+        .load_word_lists()
+        .unwrap();
+
+        assert_eq!(
+            prep_words("Intl Paper Corp.", &opts),
+            vec!["international", "paper", "corporation"]
+        );
+        assert_eq!(
+            prep_words("INT'L Business Machines & Co", &opts),
+            vec!["ibm", "and", "co"]
+        );
+
+        // Stopwords are removed after substitution, and phonetic coding follows
+        let opts = PreprocessingOptions {
+            stopword_lists: vec!["english".to_string()],
+            soundex: true,
+            ..opts
+        }
+        .load_word_lists()
+        .unwrap();
+        assert_eq!(prep_words("Smith & Bros", &opts), vec!["S530", "B636"]);
+
+        let file = TempFile::new("substitutions", "corp\n");
+        assert!(load_substitution_file(file.path()).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+//! Helpers shared by the unit tests
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::name::{NameGrouped, NameUngrouped};
+
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+
+/// A file in the temporary directory, removed when dropped
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// A path for a file not yet written, e.g. an output file
+    pub fn unwritten(prefix: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "yenta-{}-{}-{}",
+            prefix,
+            std::process::id(),
+            NEXT_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        TempFile { path }
+    }
+
+    /// A file holding `contents`
+    pub fn new(prefix: &str, contents: &str) -> Self {
+        let file = TempFile::unwritten(prefix);
+        fs::write(&file.path, contents).unwrap();
+        file
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.to_str().unwrap()
+    }
+
+    pub fn contents(&self) -> String {
+        fs::read_to_string(&self.path).unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+pub fn ungrouped(names: &[(&str, &str)]) -> Vec<NameUngrouped> {
+    names
+        .iter()
+        .map(|(n, i)| NameUngrouped::new(n.to_string(), i.to_string()))
+        .collect()
+}
+
+pub fn grouped(names: &[(&str, &str, &str)]) -> Vec<NameGrouped> {
+    names
+        .iter()
+        .map(|(n, i, g)| NameGrouped::new(n.to_string(), i.to_string(), g.to_string()))
+        .collect()
+}